@@ -1,13 +1,44 @@
-use crate::events::{Events, InputEvent};
-use anyhow::Result;
+use crate::{
+    bench::{BenchConfig, BenchMode, BenchResult},
+    bridge::{Bridge, BridgeConfig},
+    config::UiConfig,
+    diff::{Diff, DiffConfig, DiffKey, DiffSource, EntryState},
+    editor::{self, TextArea},
+    events::{Events, InputEvent, Session},
+    load::{LoadConfig, LoadGenerator},
+    message::Message,
+    monitor::{ConnInfo, MonitorClient, ServerStats},
+    nats::{format_headers, parse_headers, redact_url, subject_matches},
+    payload,
+    responder::{Responder, Rule},
+    services::{Endpoint, Instance},
+    setup_terminal,
+    status::ConnectionState,
+    stop_terminal,
+    system::SystemEvent,
+    template::{self, Template},
+    trace::{Tracer, BUCKETS, UNANSWERED_AFTER},
+};
+use anyhow::{anyhow, bail, Result};
 use chrono::{offset::Local, Timelike};
-use crossterm::event::{Event, KeyCode, KeyEvent};
+use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyModifiers};
+use log::error;
+use regex::Regex;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState,
+        Tabs, Wrap,
+    },
     Frame, Terminal,
 };
 use tui_logger::TuiLoggerWidget;
@@ -16,47 +47,284 @@ use unicode_width::UnicodeWidthStr;
 enum InputMode {
     Normal,
     Editing,
+    Form,
+}
+
+enum FormKind {
+    // reply subject and the session of the request
+    Reply(String, usize),
+    Responder,
+    Variables,
+    Load,
+    Bench(BenchMode),
+    Bridge,
+    Diff,
+}
+
+// popup with labeled single-line inputs
+struct Form {
+    kind: FormKind,
+    title: String,
+    fields: Vec<(String, String)>,
+    index: usize,
+}
+
+impl Form {
+    fn new<S: AsRef<str>>(kind: FormKind, title: String, labels: &[S]) -> Self {
+        Self {
+            kind,
+            title,
+            fields: labels
+                .iter()
+                .map(|l| (l.as_ref().to_string(), String::new()))
+                .collect(),
+            index: 0,
+        }
+    }
+
+    fn input(&mut self) -> &mut String {
+        &mut self.fields[self.index].1
+    }
+
+    fn value(&self, index: usize) -> &str {
+        self.fields[index].1.trim()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Messages,
+    Monitor,
+    Connections,
+    System,
+    Services,
+    Trace,
+    Responders,
+    Templates,
+    Bench,
+    Bridges,
+    Diff,
+}
+
+impl Tab {
+    const ALL: [Tab; 11] = [
+        Tab::Messages,
+        Tab::Monitor,
+        Tab::Connections,
+        Tab::System,
+        Tab::Services,
+        Tab::Trace,
+        Tab::Responders,
+        Tab::Templates,
+        Tab::Bench,
+        Tab::Bridges,
+        Tab::Diff,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Tab::Messages => "Messages",
+            Tab::Monitor => "Monitor",
+            Tab::Connections => "Connections",
+            Tab::System => "System",
+            Tab::Services => "Services",
+            Tab::Trace => "Trace",
+            Tab::Responders => "Responders",
+            Tab::Templates => "Templates",
+            Tab::Bench => "Bench",
+            Tab::Bridges => "Bridges",
+            Tab::Diff => "Diff",
+        }
+    }
+}
+
+// selection over a list drawn newest first, following the newest entry until
+// it is moved
+#[derive(Default)]
+struct Selection {
+    index: Option<usize>,
+}
+
+impl Selection {
+    // index of the selected entry in the list
+    fn get(&self, len: usize) -> Option<usize> {
+        match self.index {
+            _ if len == 0 => None,
+            Some(i) => Some(i.min(len - 1)),
+            None => Some(len - 1),
+        }
+    }
+
+    // row of the selected entry in the drawn list
+    fn row(&self, len: usize) -> Option<usize> {
+        self.get(len).map(|i| len - 1 - i)
+    }
+
+    fn newer(&mut self, len: usize) {
+        self.index = self.get(len).map(|i| (i + 1).min(len - 1));
+    }
+
+    fn older(&mut self, len: usize) {
+        self.index = self.get(len).map(|i| i.saturating_sub(1));
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ConnectionSort {
+    Cid,
+    Name,
+    Pending,
+    InMsgs,
+    OutMsgs,
+    Subscriptions,
+}
+
+impl ConnectionSort {
+    fn next(self) -> Self {
+        match self {
+            ConnectionSort::Cid => ConnectionSort::Name,
+            ConnectionSort::Name => ConnectionSort::Pending,
+            ConnectionSort::Pending => ConnectionSort::InMsgs,
+            ConnectionSort::InMsgs => ConnectionSort::OutMsgs,
+            ConnectionSort::OutMsgs => ConnectionSort::Subscriptions,
+            ConnectionSort::Subscriptions => ConnectionSort::Cid,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            ConnectionSort::Cid => "CID",
+            ConnectionSort::Name => "Name",
+            ConnectionSort::Pending => "Pending",
+            ConnectionSort::InMsgs => "In Msgs",
+            ConnectionSort::OutMsgs => "Out Msgs",
+            ConnectionSort::Subscriptions => "Subs",
+        }
+    }
+
+    // counters are sorted descending to bring the busiest clients on top
+    fn sort(self, connections: &mut [ConnInfo]) {
+        match self {
+            ConnectionSort::Cid => connections.sort_by_key(|c| c.cid),
+            ConnectionSort::Name => connections.sort_by(|a, b| a.name.cmp(&b.name)),
+            ConnectionSort::Pending => connections.sort_by_key(|c| Reverse(c.pending_bytes)),
+            ConnectionSort::InMsgs => connections.sort_by_key(|c| Reverse(c.in_msgs)),
+            ConnectionSort::OutMsgs => connections.sort_by_key(|c| Reverse(c.out_msgs)),
+            ConnectionSort::Subscriptions => connections.sort_by_key(|c| Reverse(c.subscriptions)),
+        }
+    }
 }
 
 pub struct Application {
+    sessions: Vec<Session>,
+    // index of the active session
+    session: usize,
+    // show the messages of all sessions instead of the active one
+    all_sessions: bool,
+    monitor: Option<MonitorClient>,
     input_nats_url: String,
     input_sub_subject: String,
     input_pub_subject: String,
-    input_pub_message: String,
+    input_pub_message: TextArea,
+    input_pub_headers: String,
     input_req_subject: String,
-    input_req_message: String,
-    input_username: Option<String>,
-    input_password: Option<String>,
-    input_token: Option<String>,
-    input_credentials: Option<String>,
+    input_req_message: TextArea,
     input_index: u16,
     input_mode: InputMode,
-    messages: Vec<(String, String)>,
+    tab: Tab,
+    messages: Vec<Message>,
+    message_selection: Selection,
+    advisories_only: bool,
+    tracer: Tracer,
+    form: Option<Form>,
+    responder: Responder,
+    responder_index: usize,
+    stats: Option<ServerStats>,
+    connections: Vec<ConnInfo>,
+    connection_index: usize,
+    connection_sort: ConnectionSort,
+    system_events: Vec<SystemEvent>,
+    system_selection: Selection,
+    services: Vec<Instance>,
+    service_index: usize,
+    templates: Vec<Template>,
+    template_index: usize,
+    // sequence number rendered into {{seq}}, counting every publish
+    publish_seq: u64,
+    // last values of the template variables, prefilled on the next prompt
+    template_variables: HashMap<String, String>,
+    load: Option<LoadGenerator>,
+    // settings of the last repeated publish
+    load_config: LoadConfig,
+    // messages sent at the last rate sample
+    load_sample: (Instant, u64),
+    load_rate: f64,
+    bench_results: Vec<Arc<BenchResult>>,
+    bridges: Vec<Bridge>,
+    bridge_index: usize,
+    diff: Option<Diff>,
+    diff_index: usize,
 }
 
 impl Application {
     pub fn new(
-        nats_url: String,
-        subject: String,
-        username: Option<String>,
-        password: Option<String>,
-        token: Option<String>,
-        credentials: Option<String>,
+        sessions: Vec<Session>,
+        monitor: Option<MonitorClient>,
+        responder: Responder,
+        templates: Vec<Template>,
+        ui: UiConfig,
     ) -> Self {
         Self {
-            input_nats_url: nats_url,
-            input_sub_subject: subject,
+            input_nats_url: redact_url(sessions[0].nats_client.url()),
+            input_sub_subject: sessions[0].subjects.join(", "),
+            sessions,
+            session: 0,
+            all_sessions: false,
+            monitor,
             input_pub_subject: String::new(),
-            input_pub_message: String::new(),
+            input_pub_message: TextArea::default(),
+            input_pub_headers: String::new(),
             input_req_subject: String::new(),
-            input_req_message: String::new(),
-            input_username: username,
-            input_password: password,
-            input_token: token,
-            input_credentials: credentials,
+            input_req_message: TextArea::default(),
             input_index: 0,
             input_mode: InputMode::Normal,
+            tab: ui
+                .tab
+                .and_then(|title| {
+                    Tab::ALL
+                        .iter()
+                        .find(|t| t.title().eq_ignore_ascii_case(&title))
+                        .copied()
+                })
+                .unwrap_or(Tab::Messages),
             messages: Vec::new(),
+            message_selection: Selection::default(),
+            advisories_only: ui.advisories_only,
+            tracer: Tracer::default(),
+            form: None,
+            responder,
+            responder_index: 0,
+            stats: None,
+            connections: Vec::new(),
+            connection_index: 0,
+            connection_sort: ConnectionSort::InMsgs,
+            system_events: Vec::new(),
+            system_selection: Selection::default(),
+            services: Vec::new(),
+            service_index: 0,
+            templates,
+            template_index: 0,
+            publish_seq: 0,
+            template_variables: HashMap::new(),
+            load: None,
+            load_config: LoadConfig::default(),
+            load_sample: (Instant::now(), 0),
+            load_rate: 0.0,
+            bench_results: Vec::new(),
+            bridges: Vec::new(),
+            bridge_index: 0,
+            diff: None,
+            diff_index: 0,
         }
     }
 
@@ -64,32 +332,45 @@ impl Application {
         terminal.clear()?;
 
         let mut events = Events::new(
-            self.input_nats_url.clone(),
-            self.input_sub_subject.clone(),
-            self.input_username.clone(),
-            self.input_password.clone(),
-            self.input_token.clone(),
-            self.input_credentials.clone(),
+            self.sessions.clone(),
+            self.monitor.clone(),
+            self.responder.clone(),
         );
 
         loop {
             terminal.draw(|f| {
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+                    .split(f.size());
+
                 // main chunk
                 let chunks = Layout::default()
                     .direction(Direction::Horizontal)
                     .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                    .split(f.size());
+                    .split(rows[0]);
 
                 self.draw_left_chunk(chunks[0], f);
                 self.draw_right_chunk(chunks[1], f);
+                self.draw_status_bar(rows[1], f);
+
+                if let Some(form) = &self.form {
+                    draw_form(form, f);
+                }
             })?;
 
             // handle events
             match events.next()? {
                 InputEvent::Input(input) => {
-                    if let Event::Key(KeyEvent { code, .. }) = input {
+                    if let Event::Key(KeyEvent { code, modifiers }) = input {
                         match self.input_mode {
                             InputMode::Normal => match code {
+                                KeyCode::Enter if self.tab == Tab::Services => {
+                                    self.fill_request_from_endpoint(&events)
+                                }
+                                KeyCode::Enter if self.tab == Tab::Templates => {
+                                    self.fill_publish_from_template()
+                                }
                                 KeyCode::Enter => {
                                     self.input_mode = InputMode::Editing;
                                 }
@@ -97,18 +378,144 @@ impl Application {
                                     events.drain();
                                     break;
                                 }
-                                KeyCode::Char('p') => events.publish(
-                                    self.input_pub_subject.clone(),
-                                    self.input_pub_message.clone(),
+                                KeyCode::Char('p') => self.publish(&events),
+                                KeyCode::Char('c') => events.reconnect(),
+                                KeyCode::Char(']') => self.select_session(
+                                    (self.session + 1) % self.sessions.len(),
+                                    &mut events,
                                 ),
-                                KeyCode::Char('r') => events.request(
-                                    self.input_req_subject.clone(),
-                                    self.input_req_message.clone(),
+                                KeyCode::Char('[') => self.select_session(
+                                    (self.session + self.sessions.len() - 1) % self.sessions.len(),
+                                    &mut events,
                                 ),
+                                KeyCode::Char('L') => self.open_load_form(),
+                                KeyCode::Char('C') => match &self.load {
+                                    Some(load) if load.is_running() => load.stop(),
+                                    _ => self.start_load(None, &events),
+                                },
+                                KeyCode::Char('X') => {
+                                    if let Some(load) = &self.load {
+                                        load.stop()
+                                    }
+                                }
+                                KeyCode::Char('r') => {
+                                    match payload::decode(&self.input_req_message.text()) {
+                                        Ok(data) => {
+                                            events.request(self.input_req_subject.clone(), data)
+                                        }
+                                        Err(err) => error!("{:#}", err),
+                                    }
+                                }
+                                KeyCode::Char(c @ '0'..='9') => {
+                                    // 0 selects the tenth tab
+                                    self.select_tab((c as usize - '0' as usize + 9) % 10, &events)
+                                }
+                                KeyCode::Tab => {
+                                    let index = Tab::ALL.iter().position(|t| *t == self.tab);
+                                    self.select_tab(
+                                        (index.unwrap_or(0) + 1) % Tab::ALL.len(),
+                                        &events,
+                                    )
+                                }
+                                KeyCode::BackTab => {
+                                    let index = Tab::ALL.iter().position(|t| *t == self.tab);
+                                    self.select_tab(
+                                        (index.unwrap_or(0) + Tab::ALL.len() - 1) % Tab::ALL.len(),
+                                        &events,
+                                    )
+                                }
+                                KeyCode::Char('a') if self.tab == Tab::Messages => {
+                                    self.advisories_only = !self.advisories_only
+                                }
+                                KeyCode::Char('m') if self.tab == Tab::Messages => {
+                                    self.all_sessions = !self.all_sessions;
+                                    self.message_selection = Selection::default();
+                                }
+                                KeyCode::Char('R') if self.tab == Tab::Messages => {
+                                    self.start_reply()
+                                }
+                                KeyCode::Char('e') if self.tab == Tab::Messages => {
+                                    self.edit_selected_message()
+                                }
+                                KeyCode::Char('P') if self.tab == Tab::Messages => {
+                                    if let Some((_, m)) = self.selected_message() {
+                                        events.publish_to(
+                                            m.session,
+                                            m.subject.clone(),
+                                            &m.headers,
+                                            &m.data,
+                                        )
+                                    }
+                                }
+                                KeyCode::Char('n') if self.tab == Tab::Responders => self
+                                    .open_form(Form::new(
+                                        FormKind::Responder,
+                                        "New Responder".to_string(),
+                                        &[
+                                            "Subject",
+                                            "Payload Regex (optional)",
+                                            "Reply",
+                                            "Headers (Key: value; Key: value)",
+                                            "Delay (ms)",
+                                            "Error (code description)",
+                                        ],
+                                    )),
+                                KeyCode::Char('b') if self.tab == Tab::Bench => {
+                                    self.open_bench_form(BenchMode::PubSub)
+                                }
+                                KeyCode::Char('B') if self.tab == Tab::Bench => {
+                                    self.open_bench_form(BenchMode::RequestReply)
+                                }
+                                KeyCode::Char('n') if self.tab == Tab::Diff => {
+                                    self.open_diff_form()
+                                }
+                                KeyCode::Char('d') if self.tab == Tab::Diff => {
+                                    if let Some(diff) = self.diff.take() {
+                                        diff.stop()
+                                    }
+                                }
+                                KeyCode::Char('n') if self.tab == Tab::Bridges => {
+                                    self.open_bridge_form()
+                                }
+                                KeyCode::Char('d')
+                                    if self.tab == Tab::Bridges
+                                        && self.bridge_index < self.bridges.len() =>
+                                {
+                                    self.bridges.remove(self.bridge_index).stop();
+                                    self.bridge_index = self.bridge_index.saturating_sub(1);
+                                }
+                                KeyCode::Char('d') if self.tab == Tab::Responders => {
+                                    self.responder.remove(self.responder_index)
+                                }
+                                KeyCode::Esc if self.tab == Tab::Messages => {
+                                    self.message_selection = Selection::default()
+                                }
+                                KeyCode::Up => self.select_previous(),
+                                KeyCode::Down => self.select_next(),
+                                KeyCode::Char('s') if self.tab == Tab::Connections => {
+                                    self.connection_sort = self.connection_sort.next();
+                                    self.connection_sort.sort(&mut self.connections);
+                                }
+                                KeyCode::Char('u') if self.tab == Tab::Connections => {
+                                    self.refresh_connections(&events)
+                                }
+                                KeyCode::Char('u') if self.tab == Tab::Services => {
+                                    events.discover_services()
+                                }
                                 _ => {}
                             },
+                            InputMode::Editing
+                                if matches!(self.input_index, 3 | 6)
+                                    && code == KeyCode::Char('e')
+                                    && modifiers.contains(KeyModifiers::CONTROL) =>
+                            {
+                                self.edit_external(terminal, &events)?
+                            }
+                            InputMode::Editing if matches!(self.input_index, 3 | 6) => {
+                                self.edit_payload(code, modifiers)
+                            }
                             InputMode::Editing => match code {
-                                KeyCode::Enter => {
+                                KeyCode::Enter | KeyCode::Esc => {
                                     self.input_mode = InputMode::Normal;
                                 }
                                 KeyCode::Char(c) => {
@@ -118,143 +525,1274 @@ impl Application {
                                     self.get_input().pop();
                                 }
                                 KeyCode::Tab => {
-                                    self.input_index = (self.input_index + 1) % 6;
+                                    self.input_index = (self.input_index + 1) % 7;
+                                }
+                                _ => {}
+                            },
+                            InputMode::Form => match (code, self.form.as_mut()) {
+                                (KeyCode::Enter, Some(_)) => {
+                                    if let Some(form) = self.form.take() {
+                                        self.submit_form(form, &events);
+                                    }
+                                    self.input_mode = InputMode::Normal;
+                                }
+                                (KeyCode::Esc, _) | (_, None) => {
+                                    self.form = None;
+                                    self.input_mode = InputMode::Normal;
+                                }
+                                (KeyCode::Char(c), Some(form)) => {
+                                    form.input().push(c);
+                                }
+                                (KeyCode::Backspace, Some(form)) => {
+                                    form.input().pop();
+                                }
+                                (KeyCode::Tab, Some(form)) => {
+                                    form.index = (form.index + 1) % form.fields.len();
+                                }
+                                (KeyCode::BackTab, Some(form)) => {
+                                    form.index =
+                                        (form.index + form.fields.len() - 1) % form.fields.len();
                                 }
                                 _ => {}
                             },
                         }
                     }
                 }
-                InputEvent::Messages(topic, msg) => self.messages.push((topic, msg)),
-                InputEvent::Tick => {}
+                InputEvent::Messages(msg) => {
+                    self.messages.push(msg);
+                    self.tracer
+                        .track(self.messages.len() - 1, &mut self.messages);
+                }
+                InputEvent::Monitor(stats) => {
+                    self.set_connections(stats.connz.connections.clone());
+                    self.stats = Some(*stats);
+                }
+                InputEvent::Connections(connections) => self.set_connections(connections),
+                InputEvent::System(event) => self.system_events.push(*event),
+                InputEvent::Services(services) => {
+                    self.services = services;
+                    self.service_index = self
+                        .service_index
+                        .min(self.endpoints().count().saturating_sub(1));
+                }
+                InputEvent::Schema(subject, schema) => {
+                    // the request form may have been changed meanwhile
+                    if self.input_req_subject == subject {
+                        self.input_req_message.set_text(&schema);
+                    }
+                }
+                InputEvent::Bench(result) => self.bench_results.push(result),
+                InputEvent::Tick => self.sample_load_rate(),
             }
         }
 
         Ok(())
     }
 
+    // draw_status_bar shows the connection state, the server info, the round
+    // trip time and the reconnects
+    fn draw_status_bar<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let color = |state| match state {
+            ConnectionState::Connected => Color::Green,
+            ConnectionState::Connecting | ConnectionState::Reconnecting => Color::Yellow,
+            ConnectionState::Closed => Color::Red,
+        };
+
+        // session names colored by their state, the active one highlighted
+        let mut spans = Vec::new();
+        if self.sessions.len() > 1 {
+            for (i, session) in self.sessions.iter().enumerate() {
+                let state = session.nats_client.status().lock().unwrap().state;
+                let style = match i == self.session {
+                    true => Style::default()
+                        .fg(color(state))
+                        .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+                    false => Style::default().fg(color(state)),
+                };
+                spans.push(Span::styled(format!(" {} ", session.name), style));
+            }
+            spans.push(Span::raw(" "));
+        }
+
+        let status = self.sessions[self.session].nats_client.status();
+        let status = status.lock().unwrap();
+        spans.push(Span::styled(
+            format!(" ● {} ", status.state.title()),
+            Style::default()
+                .fg(color(status.state))
+                .add_modifier(Modifier::BOLD),
+        ));
+        let mut push = |text: String| spans.push(Span::raw(format!("│ {} ", text)));
+
+        if let Some(at) = status.retry_at {
+            push(format!(
+                "attempt {} failed, retrying in {}s, press c to retry now",
+                status.connect_attempts,
+                at.saturating_duration_since(Instant::now()).as_secs() + 1
+            ));
+        }
+
+        if let Some(server) = &status.server {
+            push(format!(
+                "{}{} ({}) v{}",
+                if status.seed { "seed server " } else { "" },
+                server.server_name,
+                server.server_id,
+                server.version
+            ));
+            push(format!("max payload {}", format_bytes(server.max_payload)));
+        } else if status.state == ConnectionState::Connected {
+            push("server unknown".to_string());
+        }
+        if let Some(rtt) = status.rtt {
+            push(format!("rtt {}", format_duration(rtt)));
+        }
+        if !status.reconnects.is_empty() {
+            let times = status
+                .reconnects
+                .iter()
+                .rev()
+                .take(3)
+                .map(|t| t.format("%H:%M:%S").to_string())
+                .collect::<Vec<_>>();
+            push(format!(
+                "reconnects {} (last {})",
+                status.reconnects.len(),
+                times.join(", ")
+            ));
+        }
+        if let Some(server) = status
+            .server
+            .as_ref()
+            .filter(|s| !s.connect_urls.is_empty())
+        {
+            push(format!("cluster {}", server.connect_urls.join(", ")));
+        }
+
+        let bar = Paragraph::new(Spans::from(spans))
+            .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+        f.render_widget(bar, chunk);
+    }
+
     fn draw_right_chunk<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
-        // nats messages
-        let messages = self
-            .messages
+        let right_chunk = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(chunk);
+
+        // tab titles
+        let titles = Tab::ALL
             .iter()
             .enumerate()
+            .map(|(i, t)| match i < 10 {
+                true => Spans::from(format!("{} {}", (i + 1) % 10, t.title())),
+                false => Spans::from(t.title()),
+            })
+            .collect::<Vec<_>>();
+
+        let tabs = Tabs::new(titles)
+            .block(Block::default().borders(Borders::ALL))
+            .select(Tab::ALL.iter().position(|t| *t == self.tab).unwrap_or(0))
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        f.render_widget(tabs, right_chunk[0]);
+
+        match self.tab {
+            Tab::Messages => self.draw_messages(right_chunk[1], f),
+            Tab::Monitor => self.draw_monitor(right_chunk[1], f),
+            Tab::Connections => self.draw_connections(right_chunk[1], f),
+            Tab::System => self.draw_system(right_chunk[1], f),
+            Tab::Services => self.draw_services(right_chunk[1], f),
+            Tab::Trace => self.draw_trace(right_chunk[1], f),
+            Tab::Responders => self.draw_responders(right_chunk[1], f),
+            Tab::Templates => self.draw_templates(right_chunk[1], f),
+            Tab::Bench => self.draw_bench(right_chunk[1], f),
+            Tab::Bridges => self.draw_bridges(right_chunk[1], f),
+            Tab::Diff => self.draw_diff(right_chunk[1], f),
+        }
+    }
+
+    fn draw_messages<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(12)].as_ref())
+            .split(chunk);
+
+        // nats messages
+        let visible = self.visible_messages().collect::<Vec<_>>();
+        let messages = visible
+            .iter()
             .rev()
-            .map(|(i, (t, m))| {
-                Spans::from(vec![
+            .map(|(i, m)| {
+                ListItem::new(Spans::from(vec![
                     Span::styled(
                         format!("[#{}] ", i),
                         Style::default()
                             .fg(Color::Yellow)
                             .add_modifier(Modifier::BOLD),
                     ),
+                    match self.sessions.len() {
+                        1 => Span::raw(""),
+                        _ => Span::styled(
+                            format!("<{}> ", self.sessions[m.session].name),
+                            Style::default().fg(Color::Magenta),
+                        ),
+                    },
                     Span::styled(
-                        format!("[{}]: ", t),
+                        format!("[{}]", m.subject),
                         Style::default().add_modifier(Modifier::BOLD),
                     ),
-                    Span::raw(format!("{}", m)),
-                ])
+                    match &m.reply {
+                        Some(reply) => Span::styled(
+                            format!(" (reply: {})", reply),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                        None => Span::raw(""),
+                    },
+                    Span::raw(": "),
+                    match &m.summary {
+                        Some(summary) => {
+                            Span::styled(summary.clone(), Style::default().fg(Color::Cyan))
+                        }
+                        None => Span::raw(m.payload.clone()),
+                    },
+                    match (m.reply.is_some(), m.link, m.latency) {
+                        (true, Some(link), Some(latency)) => Span::styled(
+                            format!(" [-> #{} {}]", link, format_duration(latency)),
+                            Style::default().fg(Color::Green),
+                        ),
+                        (false, Some(link), _) => Span::styled(
+                            format!(" [<- #{}]", link),
+                            Style::default().fg(Color::Green),
+                        ),
+                        _ => Span::raw(""),
+                    },
+                ]))
             })
             .collect::<Vec<_>>();
 
-        let messages = Paragraph::new(messages)
+        let mut title = match self.advisories_only {
+            true => format!(
+                "Messages - {} - advisories only (A: show all)",
+                self.get_time()
+            ),
+            false => format!("Messages - {}", self.get_time()),
+        };
+        if self.sessions.len() > 1 {
+            title = match self.all_sessions {
+                true => format!("{} - all connections (M: active only)", title),
+                false => format!(
+                    "{} - {} (M: all connections, [/]: switch)",
+                    title, self.sessions[self.session].name
+                ),
+            };
+        }
+
+        let messages = List::new(messages)
             .block(Block::default().borders(Borders::ALL).title(Span::styled(
-                format!("Messages - {}", self.get_time()),
+                title,
                 Style::default().add_modifier(Modifier::BOLD),
             )))
             .style(Style::default().fg(Color::White))
-            .alignment(Alignment::Left)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = ListState::default();
+        state.select(self.message_selection.row(visible.len()));
+
+        f.render_stateful_widget(messages, chunks[0], &mut state);
+
+        // details of the selected message
+        let details = match self.selected_message() {
+            Some((i, m)) => {
+                let mut details = vec![
+                    stat_line("Index", format!("#{}", i)),
+                    stat_line("Connection", self.sessions[m.session].name.clone()),
+                    stat_line("Subject", m.subject.clone()),
+                    stat_line("Reply", m.reply.clone().unwrap_or_else(|| "-".to_string())),
+                    stat_line("Received", m.time.clone()),
+                ];
+                for (k, v) in &m.headers {
+                    details.push(stat_line("Header", format!("{}: {}", k, v)));
+                }
+                match (m.reply.is_some(), m.link, m.latency) {
+                    (true, Some(link), Some(latency)) => details.push(stat_line(
+                        "Answered",
+                        format!("by #{} in {}", link, format_duration(latency)),
+                    )),
+                    (false, Some(link), _) => {
+                        details.push(stat_line("Answers", format!("#{}", link)))
+                    }
+                    _ => {}
+                }
+                if let Some(summary) = &m.summary {
+                    details.push(stat_line("Summary", summary.clone()));
+                }
+                details.push(Spans::from(""));
+                details.extend(
+                    pretty_payload(&m.payload)
+                        .lines()
+                        .map(|l| Spans::from(l.to_string())),
+                );
+                details
+            }
+            None => Vec::new(),
+        };
+
+        let details = Paragraph::new(details)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Message (UP/DOWN: select, ESC: follow newest, SHIFT+R: reply, E: edit, SHIFT+P: resend)"),
+            )
             .wrap(Wrap { trim: false });
 
-        f.render_widget(messages, chunk);
+        f.render_widget(details, chunks[1]);
     }
 
-    fn draw_left_chunk<B: Backend>(&mut self, chunk: Rect, f: &mut Frame<B>) {
-        // left chunk
-        let left_chunk = Layout::default()
+    fn draw_monitor<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let block = |title: &'static str| {
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default().add_modifier(Modifier::BOLD),
+            ))
+        };
+
+        let (monitor, stats) = match (&self.monitor, &self.stats) {
+            (Some(monitor), Some(stats)) => (monitor, stats),
+            (Some(monitor), None) => {
+                let waiting =
+                    Paragraph::new(format!("Waiting for {}...", redact_url(monitor.url())))
+                        .block(block("Monitor"));
+                f.render_widget(waiting, chunk);
+                return;
+            }
+            (None, _) => {
+                let disabled =
+                    Paragraph::new("Monitoring is disabled. Start nats-spy with --monitor-url.")
+                        .block(block("Monitor"));
+                f.render_widget(disabled, chunk);
+                return;
+            }
+        };
+
+        let rows = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Percentage(30),
-                    Constraint::Percentage(10),
+                    Constraint::Length(8),
+                    Constraint::Length(8),
+                    Constraint::Min(0),
                 ]
                 .as_ref(),
             )
             .split(chunk);
 
-        // nats server url
-        let input_nats_server = Paragraph::new(self.input_nats_url.as_ref())
-            .block(Block::default().borders(Borders::ALL).title("NATS Server"));
+        let mut cells = Vec::new();
+        for row in rows {
+            cells.extend(
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                    .split(row),
+            );
+        }
 
-        // nats subscription subject
-        let input_subject = Paragraph::new(self.input_sub_subject.as_ref())
-            .block(Block::default().borders(Borders::ALL).title("Subject"));
+        let varz = &stats.varz;
 
-        // nats publish subject
-        let input_pub_subject = Paragraph::new(self.input_pub_subject.as_ref())
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Publish Subject"),
-            )
-            .style(
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            );
+        // server information
+        let server = Paragraph::new(vec![
+            stat_line("Name", varz.server_name.clone()),
+            stat_line("ID", varz.server_id.clone()),
+            stat_line("Version", varz.version.clone()),
+            stat_line("Address", format!("{}:{}", varz.host, varz.port)),
+            stat_line("Uptime", varz.uptime.clone()),
+            stat_line("Monitor", redact_url(monitor.url())),
+        ])
+        .block(block("Server"));
 
-        // nats puslish message
-        let input_pub_message = Paragraph::new(self.input_pub_message.as_ref())
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Publish Message"),
-            )
-            .style(
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            );
+        // resource usage
+        let resources = Paragraph::new(vec![
+            stat_line("CPU", format!("{:.1}%", varz.cpu)),
+            stat_line("Memory", format_bytes(varz.mem)),
+            stat_line("Cores", varz.cores.to_string()),
+            stat_line("Max Payload", format_bytes(varz.max_payload)),
+            stat_line("Health", stats.healthz.status.clone()),
+            stat_line(
+                "Health Error",
+                stats
+                    .healthz
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ])
+        .block(block("Resources"));
 
-        // nats request subject
-        let input_req_subject = Paragraph::new(self.input_req_subject.as_ref())
+        // message traffic
+        let traffic = Paragraph::new(vec![
+            stat_line("In Msgs", varz.in_msgs.to_string()),
+            stat_line("Out Msgs", varz.out_msgs.to_string()),
+            stat_line("In Bytes", format_bytes(varz.in_bytes)),
+            stat_line("Out Bytes", format_bytes(varz.out_bytes)),
+            stat_line("Slow Consumers", varz.slow_consumers.to_string()),
+        ])
+        .block(block("Traffic"));
+
+        // connections and subscriptions
+        let connections = Paragraph::new(vec![
+            stat_line("Connections", stats.connz.num_connections.to_string()),
+            stat_line("Total Connections", varz.total_connections.to_string()),
+            stat_line("Routes", stats.routez.num_routes.to_string()),
+            stat_line("Gateways", varz.remotes.to_string()),
+            stat_line("Leaf Nodes", varz.leafnodes.to_string()),
+            stat_line("Subscriptions", stats.subsz.num_subscriptions.to_string()),
+        ])
+        .block(block("Connections"));
+
+        // jetstream usage
+        let jetstream = match &stats.jsz {
+            Some(jsz) if !jsz.disabled => vec![
+                stat_line("Streams", jsz.streams.to_string()),
+                stat_line("Consumers", jsz.consumers.to_string()),
+                stat_line("Messages", jsz.messages.to_string()),
+                stat_line("Bytes", format_bytes(jsz.bytes)),
+                stat_line(
+                    "Memory",
+                    format!(
+                        "{} / {}",
+                        format_bytes(jsz.memory),
+                        format_bytes(jsz.config.max_memory)
+                    ),
+                ),
+                stat_line(
+                    "Storage",
+                    format!(
+                        "{} / {}",
+                        format_bytes(jsz.storage),
+                        format_bytes(jsz.config.max_storage)
+                    ),
+                ),
+            ],
+            _ => vec![Spans::from("JetStream is disabled.")],
+        };
+        let jetstream = Paragraph::new(jetstream).block(block("JetStream"));
+
+        f.render_widget(server, cells[0]);
+        f.render_widget(resources, cells[1]);
+        f.render_widget(traffic, cells[2]);
+        f.render_widget(connections, cells[3]);
+        f.render_widget(jetstream, cells[4]);
+    }
+
+    fn draw_connections<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(10)].as_ref())
+            .split(chunk);
+
+        // connections table
+        let header = Row::new(vec![
+            "CID", "Name", "IP", "Lang", "Pending", "In Msgs", "Out Msgs", "RTT", "Uptime", "Subs",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = self.connections.iter().map(|c| {
+            Row::new(vec![
+                Cell::from(c.cid.to_string()),
+                Cell::from(c.name.clone()),
+                Cell::from(format!("{}:{}", c.ip, c.port)),
+                Cell::from(format!("{} {}", c.lang, c.version)),
+                Cell::from(format_bytes(c.pending_bytes)),
+                Cell::from(c.in_msgs.to_string()),
+                Cell::from(c.out_msgs.to_string()),
+                Cell::from(c.rtt.clone()),
+                Cell::from(c.uptime.clone()),
+                Cell::from(c.subscriptions.to_string()),
+            ])
+        });
+
+        let widths = [
+            Constraint::Length(5),
+            Constraint::Percentage(15),
+            Constraint::Length(21),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(5),
+        ];
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                format!(
+                    "Connections - sorted by {} (S: sort, U: refresh)",
+                    self.connection_sort.title()
+                ),
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = TableState::default();
+        if !self.connections.is_empty() {
+            state.select(Some(self.connection_index));
+        }
+
+        f.render_stateful_widget(table, chunks[0], &mut state);
+
+        // subscriptions of the selected connection matching the visible messages
+        let subscriptions = match self.connections.get(self.connection_index) {
+            Some(c) => c
+                .subscriptions_list
+                .iter()
+                .map(|sub| {
+                    let matches = self
+                        .visible_messages()
+                        .filter(|(_, m)| subject_matches(sub, &m.subject))
+                        .count();
+                    let style = match matches {
+                        0 => Style::default(),
+                        _ => Style::default().fg(Color::Green),
+                    };
+
+                    Spans::from(vec![
+                        Span::styled(sub.clone(), style.add_modifier(Modifier::BOLD)),
+                        Span::styled(format!(" - {} messages", matches), style),
+                    ])
+                })
+                .collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+
+        let subscriptions = Paragraph::new(subscriptions)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Request Subject"),
+                    .title("Subscriptions"),
             )
-            .style(
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            );
+            .wrap(Wrap { trim: false });
 
-        // nats request message
-        let input_req_message = Paragraph::new(self.input_req_message.as_ref())
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Request Message"),
+        f.render_widget(subscriptions, chunks[1]);
+    }
+
+    fn draw_system<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        if !self.sessions.iter().any(|s| s.system) {
+            let disabled = Paragraph::new(
+                "System events are disabled. Start nats-spy with --system using system account credentials.",
             )
-            .style(
-                Style::default()
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            );
+            .block(Block::default().borders(Borders::ALL).title("System"))
+            .wrap(Wrap { trim: false });
+            f.render_widget(disabled, chunk);
+            return;
+        }
 
-        // log widget
-        let logs: TuiLoggerWidget =
-            TuiLoggerWidget::default().block(Block::default().title("Logs").borders(Borders::ALL));
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(chunk);
 
-        // help message
-        let help = match self.input_mode {
-            InputMode::Normal => {
+        // decoded system events
+        let header = Row::new(vec!["Time", "Event", "Who", "From", "Detail"]).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = self.system_events.iter().rev().map(|e| {
+            let style = match e.title() {
+                "CONNECT" => Style::default().fg(Color::Green),
+                "DISCONNECT" => Style::default().fg(Color::Magenta),
+                "AUTH ERROR" => Style::default().fg(Color::Red),
+                _ => Style::default(),
+            };
+
+            Row::new(vec![
+                Cell::from(e.time.clone()),
+                Cell::from(e.title()).style(style),
+                Cell::from(e.who()),
+                Cell::from(e.host()),
+                Cell::from(e.detail()),
+            ])
+        });
+
+        let widths = [
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Percentage(25),
+            Constraint::Length(15),
+            Constraint::Percentage(50),
+        ];
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                format!("System Events - {}", self.system_events.len()),
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = TableState::default();
+        state.select(self.system_selection.row(self.system_events.len()));
+
+        f.render_stateful_widget(table, chunks[0], &mut state);
+
+        // raw json of the selected event
+        let raw = match self
+            .system_selection
+            .get(self.system_events.len())
+            .and_then(|i| self.system_events.get(i))
+        {
+            Some(e) => match serde_json::from_str::<serde_json::Value>(&e.raw) {
+                Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|_| e.raw.clone()),
+                Err(_) => e.raw.clone(),
+            },
+            None => String::new(),
+        };
+
+        let detail = Paragraph::new(raw)
+            .block(Block::default().borders(Borders::ALL).title("Raw"))
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(detail, chunks[1]);
+    }
+
+    fn draw_services<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(10)].as_ref())
+            .split(chunk);
+
+        // service endpoints
+        let header = Row::new(vec![
+            "Service", "Version", "Instance", "Endpoint", "Subject", "Requests", "Errors",
+            "Avg Time",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = self.endpoints().map(|(i, e)| {
+            let errors = match e.stats.num_errors {
+                0 => Style::default(),
+                _ => Style::default().fg(Color::Red),
+            };
+
+            Row::new(vec![
+                Cell::from(i.service.clone()),
+                Cell::from(i.version.clone()),
+                Cell::from(i.id.clone()),
+                Cell::from(e.info.name.clone()),
+                Cell::from(e.info.subject.clone()),
+                Cell::from(e.stats.num_requests.to_string()),
+                Cell::from(e.stats.num_errors.to_string()).style(errors),
+                Cell::from(format!(
+                    "{:.3} ms",
+                    e.stats.average_processing_time as f64 / 1_000_000.0
+                )),
+            ])
+        });
+
+        let widths = [
+            Constraint::Percentage(15),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Percentage(15),
+            Constraint::Percentage(20),
+            Constraint::Length(8),
+            Constraint::Length(6),
+            Constraint::Length(10),
+        ];
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                format!(
+                    "Services - {} instances (ENTER: fill request, U: refresh)",
+                    self.services.len()
+                ),
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = TableState::default();
+        if self.endpoints().next().is_some() {
+            state.select(Some(self.service_index));
+        }
+
+        f.render_stateful_widget(table, chunks[0], &mut state);
+
+        // details of the selected endpoint
+        let details = match self.endpoints().nth(self.service_index) {
+            Some((i, e)) => {
+                let mut details = vec![
+                    stat_line("Description", i.description.clone()),
+                    stat_line("Queue Group", e.info.queue_group.clone()),
+                    stat_line("Last Error", e.stats.last_error.clone()),
+                ];
+                if let Some(schema) = &e.info.schema {
+                    details.push(stat_line("Request Schema", schema.request.clone()));
+                    details.push(stat_line("Response Schema", schema.response.clone()));
+                }
+                for (k, v) in e.info.metadata.iter().flatten() {
+                    details.push(stat_line(k, v.clone()));
+                }
+                details
+            }
+            None => Vec::new(),
+        };
+
+        let details = Paragraph::new(details)
+            .block(Block::default().borders(Borders::ALL).title("Endpoint"))
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(details, chunks[1]);
+    }
+
+    fn draw_trace<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(chunk);
+
+        // latency per request subject
+        let mut buckets = BUCKETS
+            .iter()
+            .map(|b| format!("<={}", format_duration(*b)))
+            .collect::<Vec<_>>();
+        buckets.push(format!(">{}", format_duration(BUCKETS[BUCKETS.len() - 1])));
+
+        let header = Row::new(vec![
+            "Subject".to_string(),
+            "Count".to_string(),
+            "Avg".to_string(),
+            "P50".to_string(),
+            "P99".to_string(),
+            "Max".to_string(),
+            format!("Histogram ({})", buckets.join(" ")),
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = self.tracer.latencies().iter().map(|(subject, l)| {
+            Row::new(vec![
+                subject.clone(),
+                l.count().to_string(),
+                format_duration(l.average()),
+                format_duration(l.percentile(0.5)),
+                format_duration(l.percentile(0.99)),
+                format_duration(l.max()),
+                histogram(&l.histogram),
+            ])
+        });
+
+        let widths = [
+            Constraint::Percentage(25),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Percentage(40),
+        ];
+
+        let latencies = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                "Request Latency",
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths);
+
+        f.render_widget(latencies, chunks[0]);
+
+        // requests without reply
+        let unanswered = self.tracer.unanswered();
+        let pending = unanswered
+            .iter()
+            .rev()
+            .map(|p| {
+                Spans::from(vec![
+                    Span::styled(
+                        format!("[#{}] ", p.index),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("[{}]", p.subject),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(format!(
+                        " reply {} waiting {}s",
+                        p.reply,
+                        p.sent.elapsed().as_secs()
+                    )),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let pending = Paragraph::new(pending)
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Unanswered Requests - {} (no reply after {}s)",
+                unanswered.len(),
+                UNANSWERED_AFTER.as_secs()
+            )))
+            .style(Style::default().fg(Color::Red))
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(pending, chunks[1]);
+    }
+
+    fn draw_responders<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let rules = self.responder.rules();
+
+        let header = Row::new(vec![
+            "Subject", "Payload", "Reply", "Headers", "Delay", "Error", "Hits",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = rules.iter().map(|r| {
+            Row::new(vec![
+                r.subject.clone(),
+                r.payload
+                    .as_ref()
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "*".to_string()),
+                r.reply.clone(),
+                r.headers
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                format!("{}ms", r.delay.as_millis()),
+                r.error
+                    .as_ref()
+                    .map(|(code, description)| format!("{} {}", code, description))
+                    .unwrap_or_default(),
+                r.hits.to_string(),
+            ])
+        });
+
+        let widths = [
+            Constraint::Percentage(20),
+            Constraint::Percentage(15),
+            Constraint::Percentage(25),
+            Constraint::Percentage(15),
+            Constraint::Length(7),
+            Constraint::Length(10),
+            Constraint::Length(6),
+        ];
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                "Responders (N: new rule, D: delete rule)",
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = TableState::default();
+        if !rules.is_empty() {
+            state.select(Some(self.responder_index.min(rules.len() - 1)));
+        }
+
+        f.render_stateful_widget(table, chunk, &mut state);
+    }
+
+    fn draw_bridges<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let header = Row::new(vec![
+            "From",
+            "To",
+            "Subject",
+            "Rewrite",
+            "Forwarded",
+            "Failed",
+            "State",
+            "Last Error",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = self.bridges.iter().map(|b| {
+            Row::new(vec![
+                self.sessions[b.config.from].name.clone(),
+                self.sessions[b.config.to].name.clone(),
+                b.config.subject.clone(),
+                b.config
+                    .rewrite
+                    .as_ref()
+                    .map(|(regex, replacement)| format!("{} -> {}", regex, replacement))
+                    .unwrap_or_default(),
+                b.forwarded().to_string(),
+                b.failed().to_string(),
+                match b.is_running() {
+                    true => "running",
+                    false => "waiting",
+                }
+                .to_string(),
+                b.last_error().unwrap_or_default(),
+            ])
+        });
+
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Percentage(20),
+            Constraint::Percentage(25),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Percentage(25),
+        ];
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                "Bridges (N: new bridge, D: stop bridge)",
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = TableState::default();
+        if !self.bridges.is_empty() {
+            state.select(Some(self.bridge_index.min(self.bridges.len() - 1)));
+        }
+
+        f.render_stateful_widget(table, chunk, &mut state);
+    }
+
+    fn draw_diff<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let diff = match &self.diff {
+            Some(diff) => diff,
+            None => {
+                let empty = Paragraph::new(
+                    "Press N to compare the messages of two subjects or two connections.",
+                )
+                .block(Block::default().borders(Borders::ALL).title("Diff"))
+                .wrap(Wrap { trim: false });
+                f.render_widget(empty, chunk);
+                return;
+            }
+        };
+        let state = diff.state.lock().unwrap();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(12)].as_ref())
+            .split(chunk);
+
+        let source = |s: &DiffSource| format!("{}:{}", self.sessions[s.session].name, s.subject);
+        let states = state.entries.iter().map(|e| e.state()).collect::<Vec<_>>();
+        let count = |state: EntryState| states.iter().filter(|s| **s == state).count();
+        let skews = state
+            .entries
+            .iter()
+            .filter_map(|e| e.skew())
+            .collect::<Vec<_>>();
+        let title = format!(
+            "{} <-> {} by {} - matched {}, different {}, missing left {}, missing right {}, pending {}, avg skew {:+.1}ms, unkeyed {}/{} (N: new, D: stop)",
+            source(&diff.config.left),
+            source(&diff.config.right),
+            diff.config.key,
+            count(EntryState::Matched),
+            count(EntryState::Different),
+            count(EntryState::MissingLeft),
+            count(EntryState::MissingRight),
+            count(EntryState::Pending),
+            skews.iter().sum::<f64>() / skews.len().max(1) as f64,
+            state.unkeyed[0],
+            state.unkeyed[1],
+        );
+
+        let header = Row::new(vec!["Key", "Left", "Right", "Skew", "State"]).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let time = |m: &Option<Message>| {
+            m.as_ref()
+                .map(|m| m.time.clone())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let rows = state.entries.iter().zip(&states).map(|(e, s)| {
+            let color = match s {
+                EntryState::Matched => Color::Green,
+                EntryState::Different => Color::Yellow,
+                EntryState::MissingLeft | EntryState::MissingRight => Color::Red,
+                EntryState::Pending => Color::DarkGray,
+            };
+            Row::new(vec![
+                e.key.clone(),
+                time(&e.left),
+                time(&e.right),
+                e.skew()
+                    .map(|skew| format!("{:+.1}ms", skew))
+                    .unwrap_or_default(),
+                s.title().to_string(),
+            ])
+            .style(Style::default().fg(color))
+        });
+
+        let widths = [
+            Constraint::Percentage(30),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(12),
+            Constraint::Length(14),
+        ];
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut table_state = TableState::default();
+        if !state.entries.is_empty() {
+            table_state.select(Some(self.diff_index.min(state.entries.len() - 1)));
+        }
+
+        f.render_stateful_widget(table, chunks[0], &mut table_state);
+
+        // differences and payloads of the selected entry
+        let details = match table_state.selected().map(|i| &state.entries[i]) {
+            Some(e) => {
+                let mut details = vec![stat_line("Key", e.key.clone())];
+                for difference in e.differences() {
+                    details.push(stat_line("Difference", difference));
+                }
+                for (label, m) in [("Left", &e.left), ("Right", &e.right)] {
+                    details.push(stat_line(
+                        label,
+                        m.as_ref()
+                            .map(|m| m.payload.clone())
+                            .unwrap_or_else(|| "-".to_string()),
+                    ));
+                }
+                details
+            }
+            None => Vec::new(),
+        };
+
+        let details = Paragraph::new(details)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Entry (UP/DOWN: select)"),
+            )
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(details, chunks[1]);
+    }
+
+    fn draw_templates<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let header = Row::new(vec!["Name", "Subject", "Payload", "Headers"]).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = self.templates.iter().map(|t| {
+            Row::new(vec![
+                t.name.clone(),
+                t.subject.clone(),
+                t.payload.lines().next().unwrap_or_default().to_string(),
+                t.headers.clone(),
+            ])
+        });
+
+        let widths = [
+            Constraint::Percentage(20),
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+            Constraint::Percentage(20),
+        ];
+
+        let title = match self.templates.is_empty() {
+            true => "Templates (start nats-spy with --templates)",
+            false => "Templates (ENTER: fill publish message)",
+        };
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                title,
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = TableState::default();
+        if !self.templates.is_empty() {
+            state.select(Some(self.template_index.min(self.templates.len() - 1)));
+        }
+
+        f.render_stateful_widget(table, chunk, &mut state);
+    }
+
+    fn draw_bench<B: Backend>(&self, chunk: Rect, f: &mut Frame<B>) {
+        let header = Row::new(vec![
+            "Mode", "Msgs", "Size", "Elapsed", "Msgs/sec", "P50", "P90", "P99", "Max",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows = self.bench_results.iter().rev().map(|r| {
+            Row::new(vec![
+                r.config.mode.to_string(),
+                format!("{}/{}", r.received, r.config.count),
+                format_bytes(r.config.size as u64),
+                format_duration(r.elapsed),
+                format!("{:.0}", r.rate()),
+                format_duration(r.latencies.percentile(0.5)),
+                format_duration(r.latencies.percentile(0.9)),
+                format_duration(r.latencies.percentile(0.99)),
+                format_duration(r.latencies.max()),
+            ])
+        });
+
+        let widths = [
+            Constraint::Length(14),
+            Constraint::Length(13),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+        ];
+
+        let table = Table::new(rows)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                "Benchmarks (B: pub/sub, SHIFT+B: request/reply)",
+                Style::default().add_modifier(Modifier::BOLD),
+            )))
+            .widths(&widths);
+
+        f.render_widget(table, chunk);
+    }
+
+    fn draw_left_chunk<B: Backend>(&mut self, chunk: Rect, f: &mut Frame<B>) {
+        // left chunk
+        let left_chunk = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(editor_height(&self.input_pub_message)),
+                    Constraint::Length(3),
+                    Constraint::Length(3),
+                    Constraint::Length(editor_height(&self.input_req_message)),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(10),
+                ]
+                .as_ref(),
+            )
+            .split(chunk);
+
+        // nats server url
+        let input_nats_server = Paragraph::new(self.input_nats_url.as_ref())
+            .block(Block::default().borders(Borders::ALL).title("NATS Server"));
+
+        // nats subscription subject
+        let input_subject = Paragraph::new(self.input_sub_subject.as_ref())
+            .block(Block::default().borders(Borders::ALL).title("Subject"));
+
+        // nats publish subject
+        let input_pub_subject = Paragraph::new(self.input_pub_subject.as_ref())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Publish Subject"),
+            )
+            .style(
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        // nats puslish message
+        let pub_title = match self.load_status() {
+            Some(status) => format!("Publish Message - {}", status),
+            None => "Publish Message".to_string(),
+        };
+        let input_pub_message = draw_editor(&self.input_pub_message, &pub_title, left_chunk[3]);
+
+        // nats publish headers
+        let input_pub_headers = Paragraph::new(self.input_pub_headers.as_ref())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Publish Headers (Key: value; Key: value)"),
+            )
+            .style(
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        // nats request subject
+        let input_req_subject = Paragraph::new(self.input_req_subject.as_ref())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Request Subject"),
+            )
+            .style(
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        // nats request message
+        let input_req_message =
+            draw_editor(&self.input_req_message, "Request Message", left_chunk[6]);
+
+        // log widget
+        let logs: TuiLoggerWidget =
+            TuiLoggerWidget::default().block(Block::default().title("Logs").borders(Borders::ALL));
+
+        // help message
+        let help = match self.input_mode {
+            InputMode::Normal => {
                 vec![
                     Spans::from(vec![
                         Span::raw("Press "),
@@ -295,12 +1833,26 @@ impl Application {
                     Spans::from(vec![
                         Span::raw("Press "),
                         Span::styled(
-                            "ENTER",
+                            "ESC",
                             Style::default()
                                 .add_modifier(Modifier::BOLD)
                                 .fg(Color::Blue),
                         ),
-                        Span::raw(" to stop editing."),
+                        Span::raw(" to stop editing, "),
+                        Span::styled(
+                            "CTRL+F",
+                            Style::default()
+                                .add_modifier(Modifier::BOLD)
+                                .fg(Color::Cyan),
+                        ),
+                        Span::raw(" to format JSON, "),
+                        Span::styled(
+                            "CTRL+E",
+                            Style::default()
+                                .add_modifier(Modifier::BOLD)
+                                .fg(Color::Cyan),
+                        ),
+                        Span::raw(" to open $EDITOR."),
                     ]),
                     Spans::from(vec![
                         Span::raw("Press "),
@@ -310,8 +1862,44 @@ impl Application {
                                 .add_modifier(Modifier::BOLD)
                                 .fg(Color::Magenta),
                         ),
+                        Span::raw(" to move to the next input, "),
+                        Span::styled(
+                            "SHIFT+TAB",
+                            Style::default()
+                                .add_modifier(Modifier::BOLD)
+                                .fg(Color::Magenta),
+                        ),
+                        Span::raw(" inside a payload."),
+                    ]),
+                ]
+            }
+            InputMode::Form => {
+                vec![
+                    Spans::from(vec![
+                        Span::raw("Press "),
+                        Span::styled(
+                            "ENTER",
+                            Style::default()
+                                .add_modifier(Modifier::BOLD)
+                                .fg(Color::Blue),
+                        ),
+                        Span::raw(" to submit, "),
+                        Span::styled(
+                            "TAB",
+                            Style::default()
+                                .add_modifier(Modifier::BOLD)
+                                .fg(Color::Magenta),
+                        ),
                         Span::raw(" to move cursor."),
                     ]),
+                    Spans::from(vec![
+                        Span::raw("Press "),
+                        Span::styled(
+                            "ESC",
+                            Style::default().add_modifier(Modifier::BOLD).fg(Color::Red),
+                        ),
+                        Span::raw(" to cancel."),
+                    ]),
                 ]
             }
         };
@@ -324,19 +1912,599 @@ impl Application {
         f.render_widget(input_subject, left_chunk[1]);
         f.render_widget(input_pub_subject, left_chunk[2]);
         f.render_widget(input_pub_message, left_chunk[3]);
-        f.render_widget(input_req_subject, left_chunk[4]);
-        f.render_widget(input_req_message, left_chunk[5]);
-        f.render_widget(logs, left_chunk[6]);
-        f.render_widget(help_message, left_chunk[7]);
+        f.render_widget(input_pub_headers, left_chunk[4]);
+        f.render_widget(input_req_subject, left_chunk[5]);
+        f.render_widget(input_req_message, left_chunk[6]);
+        f.render_widget(logs, left_chunk[7]);
+        f.render_widget(help_message, left_chunk[8]);
 
         // set cursor for editing mode
         match self.input_mode {
-            InputMode::Normal => {}
+            InputMode::Normal | InputMode::Form => {}
             InputMode::Editing => self.set_cursor(left_chunk, f),
         }
     }
 
+    // messages of the active session passing the advisory filter with their
+    // index
+    fn visible_messages(&self) -> impl DoubleEndedIterator<Item = (usize, &Message)> {
+        let advisories_only = self.advisories_only;
+        let session = Some(self.session).filter(|_| !self.all_sessions);
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(move |(_, m)| !advisories_only || m.summary.is_some())
+            .filter(move |(_, m)| session.is_none_or(|s| m.session == s))
+    }
+
+    // select_session switches publishing, requests and the message list to
+    // another session
+    fn select_session(&mut self, index: usize, events: &mut Events) {
+        self.session = index;
+        self.input_nats_url = redact_url(self.sessions[index].nats_client.url());
+        self.input_sub_subject = self.sessions[index].subjects.join(", ");
+        self.message_selection = Selection::default();
+        events.select_session(index);
+    }
+
+    // selected message of the messages tab with its index
+    fn selected_message(&self) -> Option<(usize, &Message)> {
+        let visible = self.visible_messages().collect::<Vec<_>>();
+        self.message_selection
+            .get(visible.len())
+            .map(|i| visible[i])
+    }
+
+    // open the reply editor for the selected message
+    fn start_reply(&mut self) {
+        let (reply, session) = match self.selected_message() {
+            Some((_, m)) => (m.reply.clone(), m.session),
+            None => return,
+        };
+
+        match reply {
+            Some(reply) => self.open_form(Form::new(
+                FormKind::Reply(reply.clone(), session),
+                format!("Reply to {}", reply),
+                &["Message"],
+            )),
+            None => error!("Selected message has no reply subject."),
+        }
+    }
+
+    // edit_selected_message copies the selected message into the publish
+    // inputs to resend it after editing
+    fn edit_selected_message(&mut self) {
+        let (subject, payload, headers) = match self.selected_message() {
+            Some((_, m)) => (
+                m.subject.clone(),
+                payload::encode(&m.data),
+                format_headers(&m.headers),
+            ),
+            None => return,
+        };
+
+        self.input_pub_subject = subject;
+        self.input_pub_message.set_text(&payload);
+        self.input_pub_headers = headers;
+        self.input_index = 3;
+        self.input_mode = InputMode::Editing;
+    }
+
+    fn open_form(&mut self, form: Form) {
+        self.form = Some(form);
+        self.input_mode = InputMode::Form;
+    }
+
+    fn submit_form(&mut self, form: Form, events: &Events) {
+        match &form.kind {
+            FormKind::Reply(subject, session) => match payload::decode(&form.fields[0].1) {
+                Ok(data) => events.publish_to(*session, subject.clone(), &[], &data),
+                Err(err) => error!("{:#}", err),
+            },
+            FormKind::Responder => {
+                let payload = Some(form.value(1)).filter(|p| !p.is_empty());
+                let delay = match form.value(4) {
+                    "" => Ok(0),
+                    d => d.parse::<u64>(),
+                };
+                let error = match form.value(5).split_once(' ') {
+                    Some((code, description)) => code
+                        .parse::<u16>()
+                        .map(|code| Some((code, description.trim().to_string()))),
+                    None if form.value(5).is_empty() => Ok(None),
+                    None => form
+                        .value(5)
+                        .parse::<u16>()
+                        .map(|code| Some((code, String::new()))),
+                };
+
+                let rule = match (delay, error) {
+                    (Ok(delay), Ok(error)) => Rule::new(
+                        form.value(0).to_string(),
+                        payload,
+                        form.fields[2].1.clone(),
+                        parse_headers(form.value(3)),
+                        Duration::from_millis(delay),
+                        error,
+                    ),
+                    (Err(err), _) | (_, Err(err)) => {
+                        error!("Invalid responder rule. {}", err);
+                        return;
+                    }
+                };
+
+                match rule {
+                    Ok(rule) if !rule.subject.is_empty() => events.add_responder(rule),
+                    Ok(_) => error!("Subject is empty!"),
+                    Err(err) => error!("{}", err),
+                }
+            }
+            FormKind::Load => {
+                let count = match form.value(0) {
+                    "" => Ok(None),
+                    c => c.parse::<u64>().map(Some).map_err(|e| e.to_string()),
+                };
+                let rate = match form.value(1) {
+                    "" => Ok(0.0),
+                    r => r.parse::<f64>().map_err(|e| e.to_string()),
+                };
+                let threads = match form.value(2) {
+                    "" => Ok(1),
+                    t => t.parse::<usize>().map_err(|e| e.to_string()),
+                };
+                let size = match form.value(3).split_once('-') {
+                    _ if form.value(3).is_empty() => Ok(None),
+                    Some((min, max)) => min
+                        .trim()
+                        .parse::<usize>()
+                        .and_then(|min| Ok(Some((min, max.trim().parse::<usize>()?))))
+                        .map_err(|e| e.to_string()),
+                    None => form
+                        .value(3)
+                        .parse::<usize>()
+                        .map(|size| Some((size, size)))
+                        .map_err(|e| e.to_string()),
+                };
+
+                match (count, rate, threads, size) {
+                    (_, _, _, Ok(Some((min, max)))) if min > max => {
+                        error!(
+                            "Invalid repeat publish. Payload size {} is above {}",
+                            min, max
+                        )
+                    }
+                    (Ok(count), Ok(rate), Ok(threads), Ok(size)) => {
+                        self.load_config.rate = rate;
+                        self.load_config.threads = threads.max(1);
+                        self.load_config.size = size;
+                        self.start_load(count, events);
+                    }
+                    (Err(err), ..) | (_, Err(err), ..) | (.., Err(err), _) | (.., Err(err)) => {
+                        error!("Invalid repeat publish. {}", err)
+                    }
+                }
+            }
+            FormKind::Bench(mode) => {
+                match (form.value(0).parse::<u64>(), form.value(1).parse::<usize>()) {
+                    (Ok(count), Ok(size)) if count > 0 => events.bench(BenchConfig {
+                        mode: *mode,
+                        count,
+                        size,
+                    }),
+                    (Ok(_), Ok(_)) => error!("Invalid benchmark. Count must be above 0"),
+                    (Err(err), _) | (_, Err(err)) => error!("Invalid benchmark. {}", err),
+                }
+            }
+            FormKind::Bridge => match self.bridge_config(&form) {
+                Ok(config) => {
+                    if let Some(bridge) = events.start_bridge(config) {
+                        self.bridges.push(bridge);
+                        self.bridge_index = self.bridges.len() - 1;
+                    }
+                }
+                Err(err) => error!("Invalid bridge. {}", err),
+            },
+            FormKind::Diff => match self.diff_config(&form) {
+                Ok(config) => {
+                    if let Some(diff) = self.diff.take() {
+                        diff.stop();
+                    }
+                    self.diff = events.start_diff(config);
+                    self.diff_index = 0;
+                }
+                Err(err) => error!("Invalid comparison. {}", err),
+            },
+            FormKind::Variables => {
+                for (name, value) in &form.fields {
+                    self.template_variables.insert(name.clone(), value.clone());
+                }
+                self.send_publish(events);
+            }
+        }
+    }
+
+    // open_diff_form asks for two sources, prefilled with the active session
+    // and the next one
+    fn open_diff_form(&mut self) {
+        let mut form = Form::new(
+            FormKind::Diff,
+            "Compare Messages".to_string(),
+            &[
+                "Left Connection",
+                "Left Subject",
+                "Right Connection",
+                "Right Subject",
+                "Match Key (header:Name or JSON path)",
+            ],
+        );
+        form.fields[0].1 = self.sessions[self.session].name.clone();
+        form.fields[2].1 = self.sessions[(self.session + 1) % self.sessions.len()]
+            .name
+            .clone();
+        self.open_form(form);
+    }
+
+    fn diff_config(&self, form: &Form) -> Result<DiffConfig> {
+        let source = |name: usize, subject: usize| -> Result<DiffSource> {
+            let session = match self
+                .sessions
+                .iter()
+                .position(|s| s.name == form.value(name))
+            {
+                Some(session) => session,
+                None => bail!("Connection '{}' not found.", form.value(name)),
+            };
+            if form.value(subject).is_empty() {
+                bail!("Subject is empty.");
+            }
+            Ok(DiffSource {
+                session,
+                subject: form.value(subject).to_string(),
+            })
+        };
+        let (left, right) = (source(0, 1)?, source(2, 3)?);
+        if left.session == right.session && left.subject == right.subject {
+            bail!("Sources must differ.");
+        }
+        if form.value(4).is_empty() {
+            bail!("Match key is empty.");
+        }
+
+        Ok(DiffConfig {
+            left,
+            right,
+            key: DiffKey::parse(form.value(4)),
+        })
+    }
+
+    // select_tab shows the tab and refreshes the data it needs
+    fn select_tab(&mut self, index: usize, events: &Events) {
+        if let Some(tab) = Tab::ALL.get(index) {
+            self.tab = *tab;
+            match self.tab {
+                Tab::Connections => self.refresh_connections(events),
+                Tab::Services => events.discover_services(),
+                _ => {}
+            }
+        }
+    }
+
+    // open_bridge_form asks for a bridge from the active session to the next
+    fn open_bridge_form(&mut self) {
+        let mut form = Form::new(
+            FormKind::Bridge,
+            "New Bridge".to_string(),
+            &[
+                "From Connection",
+                "To Connection",
+                "Subject",
+                "Rewrite Regex (optional)",
+                "Replacement ($1 for groups)",
+            ],
+        );
+        form.fields[0].1 = self.sessions[self.session].name.clone();
+        form.fields[1].1 = self.sessions[(self.session + 1) % self.sessions.len()]
+            .name
+            .clone();
+        self.open_form(form);
+    }
+
+    fn bridge_config(&self, form: &Form) -> Result<BridgeConfig> {
+        let session = |name: &str| match self.sessions.iter().position(|s| s.name == name) {
+            Some(index) => Ok(index),
+            None => Err(anyhow!("Connection '{}' not found.", name)),
+        };
+        let (from, to) = (session(form.value(0))?, session(form.value(1))?);
+        if from == to {
+            bail!("From and to connections must differ.");
+        }
+        if form.value(2).is_empty() {
+            bail!("Subject is empty.");
+        }
+
+        let rewrite = match form.value(3) {
+            "" => None,
+            regex => Some((Regex::new(regex)?, form.value(4).to_string())),
+        };
+
+        Ok(BridgeConfig {
+            from,
+            to,
+            subject: form.value(2).to_string(),
+            rewrite,
+        })
+    }
+
+    // publish sends the publish inputs, prompting the template variables first
+    fn publish(&mut self, events: &Events) {
+        let text = self.input_pub_message.text();
+        let variables =
+            template::variables(&[&self.input_pub_subject, &text, &self.input_pub_headers]);
+        if variables.is_empty() {
+            self.send_publish(events);
+            return;
+        }
+
+        let mut form = Form::new(
+            FormKind::Variables,
+            "Template Variables".to_string(),
+            &variables,
+        );
+        for (name, value) in form.fields.iter_mut() {
+            if let Some(last) = self.template_variables.get(name) {
+                *value = last.clone();
+            }
+        }
+        self.open_form(form);
+    }
+
+    // send_publish renders the placeholders of the publish inputs with fresh
+    // values and publishes the message
+    fn send_publish(&mut self, events: &Events) {
+        self.publish_seq += 1;
+        let render =
+            |text: &str| template::render(text, self.publish_seq, &self.template_variables);
+
+        let rendered = render(&self.input_pub_subject).and_then(|subject| {
+            let headers = render(&self.input_pub_headers)?;
+            let data = payload::decode(&render(&self.input_pub_message.text())?)?;
+            Ok((subject, headers, data))
+        });
+
+        match rendered {
+            Ok((subject, headers, data)) => {
+                events.publish_with_headers(subject, &parse_headers(&headers), &data)
+            }
+            Err(err) => error!("{:#}", err),
+        }
+    }
+
+    fn open_bench_form(&mut self, mode: BenchMode) {
+        let mut form = Form::new(
+            FormKind::Bench(mode),
+            format!("{} Benchmark", mode),
+            &["Count", "Payload Size (bytes)"],
+        );
+        form.fields[0].1 = "10000".to_string();
+        form.fields[1].1 = "128".to_string();
+        self.open_form(form);
+    }
+
+    fn open_load_form(&mut self) {
+        let mut form = Form::new(
+            FormKind::Load,
+            "Repeat Publish".to_string(),
+            &[
+                "Count (empty: until stopped)",
+                "Rate msgs/sec (empty: unlimited)",
+                "Publisher Threads",
+                "Payload Size (min-max, empty: publish message)",
+            ],
+        );
+        let config = &self.load_config;
+        form.fields[1].1 = match config.rate > 0.0 {
+            true => config.rate.to_string(),
+            false => String::new(),
+        };
+        form.fields[2].1 = config.threads.to_string();
+        form.fields[3].1 = match config.size {
+            Some((min, max)) => format!("{}-{}", min, max),
+            None => String::new(),
+        };
+        self.open_form(form);
+    }
+
+    // start_load repeats the publish inputs with the last load settings,
+    // stopping the running one
+    fn start_load(&mut self, count: Option<u64>, events: &Events) {
+        if self.input_pub_subject.is_empty() {
+            error!("Subject is empty!");
+            return;
+        }
+        if let Some(load) = self.load.take() {
+            load.stop();
+        }
+
+        self.load_config.subject = self.input_pub_subject.clone();
+        self.load_config.headers = self.input_pub_headers.clone();
+        self.load_config.payload = self.input_pub_message.text();
+        self.load_config.variables = self.template_variables.clone();
+        self.load_config.count = count;
+
+        self.load = Some(events.start_load(self.load_config.clone()));
+        self.load_sample = (Instant::now(), 0);
+        self.load_rate = 0.0;
+    }
+
+    // sample_load_rate updates the achieved rate of the repeated publish
+    // once a second
+    fn sample_load_rate(&mut self) {
+        let load = match &self.load {
+            Some(load) => load,
+            None => return,
+        };
+
+        let (at, sent) = self.load_sample;
+        let elapsed = at.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.load_rate = (load.sent() - sent) as f64 / elapsed.as_secs_f64();
+            self.load_sample = (Instant::now(), load.sent());
+        }
+    }
+
+    // load_status is the progress line of the repeated publish
+    fn load_status(&self) -> Option<String> {
+        let load = self.load.as_ref()?;
+        let total = match load.config.count {
+            Some(count) => format!("{}/{}", load.sent() + load.errors(), count),
+            None => (load.sent() + load.errors()).to_string(),
+        };
+
+        let status = match load.is_running() {
+            true => format!(
+                "{} sent, {:.1} msgs/sec, {} errors (X: stop)",
+                total,
+                self.load_rate,
+                load.errors()
+            ),
+            false => {
+                // a load stopped right away has no rate
+                let elapsed = load.elapsed().as_secs_f64();
+                let rate = match elapsed > 0.0 {
+                    true => load.sent() as f64 / elapsed,
+                    false => 0.0,
+                };
+                format!(
+                    "{} sent in {}, {:.1} msgs/sec, {} errors",
+                    total,
+                    format_duration(load.elapsed()),
+                    rate,
+                    load.errors()
+                )
+            }
+        };
+
+        Some(match (load.errors(), load.last_error()) {
+            (0, _) | (_, None) => status,
+            (_, Some(err)) => format!("{} - {}", status, err),
+        })
+    }
+
+    // prefill the publish inputs with the selected template
+    fn fill_publish_from_template(&mut self) {
+        let template = match self.templates.get(self.template_index) {
+            Some(template) => template.clone(),
+            None => return,
+        };
+
+        self.input_pub_subject = template.subject;
+        self.input_pub_message.set_text(&template.payload);
+        self.input_pub_headers = template.headers;
+        self.input_index = 3;
+        self.input_mode = InputMode::Editing;
+    }
+
+    fn refresh_connections(&self, events: &Events) {
+        match self.monitor {
+            Some(_) => events.refresh_monitor(),
+            None => events.request_connz(),
+        }
+    }
+
+    fn set_connections(&mut self, mut connections: Vec<ConnInfo>) {
+        self.connection_sort.sort(&mut connections);
+        self.connections = connections;
+        self.connection_index = self
+            .connection_index
+            .min(self.connections.len().saturating_sub(1));
+    }
+
+    // endpoints of all discovered service instances
+    fn endpoints(&self) -> impl Iterator<Item = (&Instance, &Endpoint)> {
+        self.services
+            .iter()
+            .flat_map(|i| i.endpoints.iter().map(move |e| (i, e)))
+    }
+
+    // prefill the request form with the selected endpoint, its request schema
+    // follows from $SRV.SCHEMA unless the info already has it
+    fn fill_request_from_endpoint(&mut self, events: &Events) {
+        let (subject, schema) = match self.endpoints().nth(self.service_index) {
+            Some((i, e)) => {
+                let schema = e.info.schema.as_ref().map(|s| s.request.clone());
+                if schema.as_deref().unwrap_or_default().is_empty() {
+                    events.request_schema(i, e);
+                }
+                (e.info.subject.clone(), schema)
+            }
+            None => return,
+        };
+
+        self.input_req_subject = subject;
+        if let Some(schema) = schema.filter(|s| !s.is_empty()) {
+            self.input_req_message.set_text(&schema);
+        }
+        self.input_index = 6;
+        self.input_mode = InputMode::Editing;
+    }
+
+    fn select_previous(&mut self) {
+        match self.tab {
+            Tab::Messages => self
+                .message_selection
+                .newer(self.visible_messages().count()),
+            Tab::Connections => self.connection_index = self.connection_index.saturating_sub(1),
+            Tab::Services => self.service_index = self.service_index.saturating_sub(1),
+            Tab::Responders => self.responder_index = self.responder_index.saturating_sub(1),
+            Tab::Templates => self.template_index = self.template_index.saturating_sub(1),
+            Tab::Bridges => self.bridge_index = self.bridge_index.saturating_sub(1),
+            Tab::Diff => self.diff_index = self.diff_index.saturating_sub(1),
+            Tab::System => self.system_selection.newer(self.system_events.len()),
+            _ => {}
+        }
+    }
+
+    fn select_next(&mut self) {
+        match self.tab {
+            Tab::Messages => self
+                .message_selection
+                .older(self.visible_messages().count()),
+            Tab::Connections if self.connection_index + 1 < self.connections.len() => {
+                self.connection_index += 1
+            }
+            Tab::System => self.system_selection.older(self.system_events.len()),
+            Tab::Services if self.service_index + 1 < self.endpoints().count() => {
+                self.service_index += 1
+            }
+            Tab::Responders if self.responder_index + 1 < self.responder.rules().len() => {
+                self.responder_index += 1
+            }
+            Tab::Templates if self.template_index + 1 < self.templates.len() => {
+                self.template_index += 1
+            }
+            Tab::Bridges if self.bridge_index + 1 < self.bridges.len() => self.bridge_index += 1,
+            Tab::Diff => {
+                if let Some(diff) = &self.diff {
+                    let len = diff.state.lock().unwrap().entries.len();
+                    self.diff_index = (self.diff_index + 1).min(len.saturating_sub(1));
+                }
+            }
+            _ => {}
+        }
+    }
+
     fn set_cursor<B: Backend>(&mut self, chunk: Vec<Rect>, f: &mut Frame<B>) {
+        let area = chunk[self.input_index as usize];
+        if let Some(editor) = self.get_editor() {
+            let (offset, gutter) = editor_view(editor, area);
+            f.set_cursor(
+                area.x + 1 + gutter + editor.cursor_width() as u16,
+                area.y + 1 + (editor.row() - offset) as u16,
+            );
+            return;
+        }
+
         f.set_cursor(
             chunk[self.input_index as usize].x + self.get_input().width() as u16 + 1,
             chunk[self.input_index as usize].y + 1,
@@ -346,9 +2514,8 @@ impl Application {
     fn get_input(&mut self) -> &mut String {
         match self.input_index {
             2 => &mut self.input_pub_subject,
-            3 => &mut self.input_pub_message,
-            4 => &mut self.input_req_subject,
-            5 => &mut self.input_req_message,
+            4 => &mut self.input_pub_headers,
+            5 => &mut self.input_req_subject,
             _ => {
                 self.input_index = 2;
                 &mut self.input_pub_subject
@@ -356,9 +2523,280 @@ impl Application {
         }
     }
 
+    // get_editor returns the multi-line editor of the payload inputs
+    fn get_editor(&mut self) -> Option<&mut TextArea> {
+        match self.input_index {
+            3 => Some(&mut self.input_pub_message),
+            6 => Some(&mut self.input_req_message),
+            _ => None,
+        }
+    }
+
+    // edit_external suspends the tui to edit the focused payload in $EDITOR
+    fn edit_external<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &Events,
+    ) -> Result<()> {
+        let text = match self.get_editor() {
+            Some(editor) => editor.text(),
+            None => return Ok(()),
+        };
+
+        events.pause_keyboard();
+        stop_terminal()?;
+        let edited = editor::edit_external(&text);
+        setup_terminal()?;
+        terminal.clear()?;
+        events.resume_keyboard();
+
+        match (edited, self.get_editor()) {
+            (Ok(edited), Some(editor)) => editor.set_text(&edited),
+            (Err(err), _) => error!("{}", err),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn edit_payload(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let editor = match self.get_editor() {
+            Some(editor) => editor,
+            None => return,
+        };
+
+        match code {
+            KeyCode::Esc => self.input_mode = InputMode::Normal,
+            // tabs belong to the payload, pasted ones too
+            KeyCode::Tab => editor.insert('\t'),
+            KeyCode::BackTab => self.input_index = (self.input_index + 1) % 7,
+            KeyCode::Char('f') if modifiers.contains(KeyModifiers::CONTROL) => editor.format_json(),
+            KeyCode::Char(c) => editor.insert(c),
+            KeyCode::Enter => editor.newline(),
+            KeyCode::Backspace => editor.backspace(),
+            KeyCode::Delete => editor.delete(),
+            KeyCode::Left => editor.left(),
+            KeyCode::Right => editor.right(),
+            KeyCode::Up => editor.up(),
+            KeyCode::Down => editor.down(),
+            KeyCode::Home => editor.home(),
+            KeyCode::End => editor.end(),
+            _ => {}
+        }
+    }
+
     fn get_time(&self) -> String {
         let now = Local::now();
 
         format!("{:02}:{:02}:{:02}", now.hour(), now.minute(), now.second())
     }
 }
+
+// height of a payload editor including its borders
+fn editor_height(editor: &TextArea) -> u16 {
+    editor.lines().len().clamp(1, 8) as u16 + 2
+}
+
+// editor_view returns the first visible line keeping the cursor in view and
+// the width of the line number gutter
+fn editor_view(editor: &TextArea, area: Rect) -> (usize, u16) {
+    let height = area.height.saturating_sub(2).max(1) as usize;
+    let offset = (editor.row() + 1).saturating_sub(height);
+    let gutter = editor.lines().len().to_string().len() as u16 + 1;
+
+    (offset, gutter)
+}
+
+// draw_editor renders the payload editor with line numbers and the json
+// parse error in its title
+fn draw_editor<'a>(editor: &'a TextArea, title: &'a str, area: Rect) -> Paragraph<'a> {
+    let (offset, gutter) = editor_view(editor, area);
+    let error_row = editor.error().map(|(row, _)| *row);
+
+    let lines = editor
+        .lines()
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let number_style = match error_row == Some(i) {
+                true => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                false => Style::default().fg(Color::DarkGray),
+            };
+            Spans::from(vec![
+                Span::styled(
+                    format!("{:>width$} ", i + 1, width = gutter as usize - 1),
+                    number_style,
+                ),
+                Span::styled(
+                    editor::expand_tabs(line),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let title = match editor.error() {
+        Some((_, err)) => Spans::from(vec![
+            Span::raw(title),
+            Span::styled(format!(" - {}", err), Style::default().fg(Color::Red)),
+        ]),
+        None => Spans::from(title),
+    };
+
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .scroll((offset as u16, 0))
+}
+
+fn stat_line(label: &str, value: String) -> Spans<'static> {
+    Spans::from(vec![
+        Span::styled(
+            format!("{}: ", label),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(value),
+    ])
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} {}", bytes, UNITS[0]),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    match duration.as_micros() {
+        us if us < 1_000 => format!("{}us", us),
+        us if us < 1_000_000 => format!("{:.1}ms", us as f64 / 1_000.0),
+        us => format!("{:.2}s", us as f64 / 1_000_000.0),
+    }
+}
+
+// histogram renders bucket counts as bars relative to the largest bucket
+fn histogram(buckets: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let max = buckets.iter().max().copied().unwrap_or(0);
+    buckets
+        .iter()
+        .map(|b| match b {
+            0 => ' ',
+            b => BARS[((*b * (BARS.len() as u64 - 1)) / max.max(1)) as usize],
+        })
+        .collect()
+}
+
+// pretty_payload indents json payloads and leaves anything else as it is
+fn pretty_payload(payload: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(payload)
+        .and_then(|value| serde_json::to_string_pretty(&value))
+        .unwrap_or_else(|_| payload.to_string())
+}
+
+// centered_rect returns a rect in the middle of r with the given width
+// percentage and height
+fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
+    let width = r.width * percent_x / 100;
+
+    Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + r.height.saturating_sub(height) / 2,
+        width,
+        height: height.min(r.height),
+    }
+}
+
+// pick_profile lets the user choose one of the config profiles before
+// connecting, ESC starts without a profile
+pub fn pick_profile<B: Backend>(
+    terminal: &mut Terminal<B>,
+    profiles: &[String],
+) -> Result<Option<usize>> {
+    let mut state = ListState::default();
+    state.select(Some(0));
+
+    loop {
+        terminal.draw(|f| {
+            let area = centered_rect(40, profiles.len() as u16 + 2, f.size());
+            let items = profiles
+                .iter()
+                .map(|p| ListItem::new(p.as_str()))
+                .collect::<Vec<_>>();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                    "Select Profile (ENTER: connect, ESC: no profile)",
+                    Style::default().add_modifier(Modifier::BOLD),
+                )))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+            f.render_widget(Clear, area);
+            f.render_stateful_widget(list, area, &mut state);
+        })?;
+
+        let selected = state.selected().unwrap_or(0);
+        if let Event::Key(KeyEvent { code, .. }) = read()? {
+            match code {
+                KeyCode::Enter => return Ok(Some(selected)),
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Up => state.select(Some(selected.saturating_sub(1))),
+                KeyCode::Down => state.select(Some((selected + 1).min(profiles.len() - 1))),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw_form<B: Backend>(form: &Form, f: &mut Frame<B>) {
+    let area = centered_rect(60, form.fields.len() as u16 * 3 + 2, f.size());
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        format!("{} (TAB: next, ENTER: submit, ESC: cancel)", form.title),
+        Style::default().add_modifier(Modifier::BOLD),
+    ));
+    let inner = block.inner(area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            form.fields
+                .iter()
+                .map(|_| Constraint::Length(3))
+                .collect::<Vec<_>>(),
+        )
+        .split(inner);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    for (i, (label, value)) in form.fields.iter().enumerate() {
+        let style = match i == form.index {
+            true => Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            false => Style::default(),
+        };
+
+        let input = Paragraph::new(value.as_ref())
+            .block(Block::default().borders(Borders::ALL).title(label.as_str()))
+            .style(style);
+        f.render_widget(input, chunks[i]);
+    }
+
+    let value = &form.fields[form.index].1;
+    f.set_cursor(
+        chunks[form.index].x + value.width() as u16 + 1,
+        chunks[form.index].y + 1,
+    );
+}
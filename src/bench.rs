@@ -0,0 +1,182 @@
+use crate::{nats::NatsClient, trace::Latencies};
+use anyhow::{bail, Result};
+use std::{
+    convert::TryInto,
+    fmt, thread,
+    time::{Duration, Instant},
+};
+
+// subjects of benchmark messages, hidden from the message list
+pub const SUBJECT_PREFIX: &str = "_NATS_SPY.bench.";
+
+// a benchmark gives up when no message arrives for this duration
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BenchMode {
+    PubSub,
+    RequestReply,
+}
+
+impl fmt::Display for BenchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BenchMode::PubSub => write!(f, "pub/sub"),
+            BenchMode::RequestReply => write!(f, "request/reply"),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct BenchConfig {
+    pub mode: BenchMode,
+    pub count: u64,
+    // payload size in bytes, at least the 8 bytes of the timestamp
+    pub size: usize,
+}
+
+pub struct BenchResult {
+    pub config: BenchConfig,
+    pub received: u64,
+    pub elapsed: Duration,
+    pub latencies: Latencies,
+}
+
+impl BenchResult {
+    pub fn rate(&self) -> f64 {
+        self.received as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl fmt::Display for BenchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        writeln!(
+            f,
+            "{} benchmark: {}/{} messages of {} bytes in {:.3}s, {:.0} msgs/sec",
+            self.config.mode,
+            self.received,
+            self.config.count,
+            self.config.size,
+            self.elapsed.as_secs_f64(),
+            self.rate()
+        )?;
+        write!(
+            f,
+            "latency p50 {:.3}ms, p90 {:.3}ms, p99 {:.3}ms, max {:.3}ms",
+            ms(self.latencies.percentile(0.5)),
+            ms(self.latencies.percentile(0.9)),
+            ms(self.latencies.percentile(0.99)),
+            ms(self.latencies.max())
+        )
+    }
+}
+
+// run measures latency and throughput against the connected server on a
+// private subject
+pub fn run(config: BenchConfig, nc: &NatsClient) -> Result<BenchResult> {
+    let subject = format!("{}{}", SUBJECT_PREFIX, uuid::Uuid::new_v4().to_simple());
+
+    match config.mode {
+        BenchMode::PubSub => pub_sub(config, subject, nc),
+        BenchMode::RequestReply => request_reply(config, subject, nc),
+    }
+}
+
+// pub_sub publishes timestamped messages and receives them on the same
+// connection
+fn pub_sub(config: BenchConfig, subject: String, nc: &NatsClient) -> Result<BenchResult> {
+    let sub = nc.subscribe(subject.clone())?;
+    let start = Instant::now();
+
+    let publisher = nc.clone();
+    thread::spawn(move || {
+        for _ in 0..config.count {
+            if publisher
+                .publish(subject.clone(), timestamped(start, config.size))
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+
+    let mut latencies = Latencies::default();
+    let mut received = 0;
+    while received < config.count {
+        match sub.next_timeout(RECEIVE_TIMEOUT) {
+            Ok(msg) => {
+                latencies.record(start.elapsed() - sent_at(&msg.data)?);
+                received += 1;
+            }
+            Err(_) => break,
+        }
+    }
+    let elapsed = start.elapsed();
+    sub.unsubscribe()?;
+
+    Ok(BenchResult {
+        config,
+        received,
+        elapsed,
+        latencies,
+    })
+}
+
+// request_reply sends requests one by one to an echo responder, replies use
+// the benchmark subject as well to keep them out of the message list
+fn request_reply(config: BenchConfig, subject: String, nc: &NatsClient) -> Result<BenchResult> {
+    let reply = format!("{}.reply", subject);
+    let replies = nc.subscribe(reply.clone())?;
+    let echo = nc.subscribe(subject.clone())?;
+    let handler = echo.clone();
+    thread::spawn(move || {
+        for msg in handler.messages() {
+            let _ = msg.respond(&msg.data);
+        }
+    });
+
+    let start = Instant::now();
+    let mut latencies = Latencies::default();
+    let mut received = 0;
+    for _ in 0..config.count {
+        let sent = Instant::now();
+        nc.publish_with_headers(
+            subject.clone(),
+            Some(reply.clone()),
+            &[],
+            timestamped(start, config.size),
+        )?;
+        match replies.next_timeout(RECEIVE_TIMEOUT) {
+            Ok(_) => {
+                latencies.record(sent.elapsed());
+                received += 1;
+            }
+            Err(_) => break,
+        }
+    }
+    let elapsed = start.elapsed();
+    echo.unsubscribe()?;
+    replies.unsubscribe()?;
+
+    Ok(BenchResult {
+        config,
+        received,
+        elapsed,
+        latencies,
+    })
+}
+
+// timestamped payload starting with the nanoseconds since start
+fn timestamped(start: Instant, size: usize) -> Vec<u8> {
+    let mut data = (start.elapsed().as_nanos() as u64).to_be_bytes().to_vec();
+    data.resize(size.max(data.len()), b'x');
+    data
+}
+
+fn sent_at(data: &[u8]) -> Result<Duration> {
+    match data.get(..8).and_then(|nanos| nanos.try_into().ok()) {
+        Some(nanos) => Ok(Duration::from_nanos(u64::from_be_bytes(nanos))),
+        None => bail!("Benchmark message without timestamp."),
+    }
+}
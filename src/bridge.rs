@@ -0,0 +1,118 @@
+use crate::{
+    bench,
+    message::Message,
+    nats::{ClientCopy, LiveSubscription, SharedClient},
+};
+use anyhow::Result;
+use regex::Regex;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+// header marking forwarded messages with their original subject, they are
+// not forwarded again so bridges in both directions do not loop
+pub const HEADER: &str = "Nats-Spy-Bridge";
+
+// BridgeConfig forwards the messages of a subject from one session to another
+#[derive(Clone)]
+pub struct BridgeConfig {
+    pub from: usize,
+    pub to: usize,
+    pub subject: String,
+    // subjects matching the regex are rewritten with the replacement
+    pub rewrite: Option<(Regex, String)>,
+}
+
+impl BridgeConfig {
+    // target subject of a forwarded message
+    pub fn rewrite(&self, subject: &str) -> String {
+        match &self.rewrite {
+            Some((regex, replacement)) => regex.replace(subject, replacement.as_str()).to_string(),
+            None => subject.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    forwarded: AtomicU64,
+    failed: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+// Bridge subscribes on one session and publishes every message on the other
+// until it is stopped, replies cannot cross servers and are dropped. Both
+// sides follow their sessions when they connect anew.
+pub struct Bridge {
+    sub: LiveSubscription,
+    counters: Arc<Counters>,
+    pub config: BridgeConfig,
+}
+
+impl Bridge {
+    // start fails when the source session is not connected
+    pub fn start(config: BridgeConfig, from: SharedClient, to: SharedClient) -> Result<Self> {
+        let counters = Arc::new(Counters::default());
+
+        let forward = config.clone();
+        let count = counters.clone();
+        let mut to = ClientCopy::new(to);
+        let sub = LiveSubscription::start(from, config.subject.clone(), move |msg| {
+            if msg.subject.starts_with(bench::SUBJECT_PREFIX) {
+                return;
+            }
+
+            let mut msg = Message::from(msg);
+            if msg
+                .headers
+                .iter()
+                .any(|(k, _)| k.eq_ignore_ascii_case(HEADER))
+            {
+                return;
+            }
+            msg.headers.push((HEADER.to_string(), msg.subject.clone()));
+
+            let result = to.get().publish_with_headers(
+                forward.rewrite(&msg.subject),
+                None,
+                &msg.headers,
+                msg.data,
+            );
+            match result {
+                Ok(_) => count.forwarded.fetch_add(1, Ordering::SeqCst),
+                Err(err) => {
+                    *count.last_error.lock().unwrap() = Some(format!("{:#}", err));
+                    count.failed.fetch_add(1, Ordering::SeqCst)
+                }
+            };
+        })?;
+
+        Ok(Self {
+            sub,
+            counters,
+            config,
+        })
+    }
+
+    pub fn stop(&self) {
+        self.sub.stop();
+    }
+
+    // is_running is false while the source session is not connected
+    pub fn is_running(&self) -> bool {
+        self.sub.is_active()
+    }
+
+    pub fn forwarded(&self) -> u64 {
+        self.counters.forwarded.load(Ordering::SeqCst)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.counters.failed.load(Ordering::SeqCst)
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.counters.last_error.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,67 @@
+use crate::nats::TlsOptions;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    // title of the tab shown on start
+    pub tab: Option<String>,
+    pub advisories_only: bool,
+}
+
+// Profile holds the settings of one connection, flags given on the command
+// line take precedence
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+    pub credentials: Option<String>,
+    // file with the nkey seed
+    pub nkey: Option<String>,
+    pub tls: TlsOptions,
+    pub subscriptions: Vec<String>,
+    pub monitor_url: Option<String>,
+    pub monitor_interval: Option<u64>,
+    pub system: bool,
+    pub responders: Option<String>,
+    pub templates: Option<String>,
+    pub ui: UiConfig,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub profile: BTreeMap<String, Profile>,
+}
+
+// config_dir is $XDG_CONFIG_HOME or ~/.config
+pub fn config_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => Some(PathBuf::from(env::var_os("HOME")?).join(".config")),
+    }
+}
+
+// default_path is nats-spy/config.toml in the XDG config directory
+pub fn default_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("nats-spy").join("config.toml"))
+}
+
+// load reads the given config file, or the default one if it exists
+pub fn load(path: Option<&str>) -> Result<Config> {
+    let path = match (path, default_path()) {
+        (Some(path), _) => PathBuf::from(path),
+        (None, Some(path)) if path.exists() => path,
+        (None, _) => return Ok(Config::default()),
+    };
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Cannot read '{}'", path.display()))?;
+
+    toml::from_str(&content).with_context(|| format!("Cannot parse '{}'", path.display()))
+}
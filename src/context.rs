@@ -0,0 +1,73 @@
+use crate::{config, nats::TlsOptions};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+// connection settings of a nats cli context, empty strings are unset
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct NatsContext {
+    url: String,
+    token: String,
+    user: String,
+    password: String,
+    creds: String,
+    nkey: String,
+    cert: String,
+    key: String,
+    ca: String,
+    tls_first: bool,
+}
+
+// load reads the nats cli context with the given name, or the one selected
+// with `nats context select`, as a profile
+pub fn load(name: Option<&str>) -> Result<config::Profile> {
+    let dir = match config::config_dir() {
+        Some(dir) => dir.join("nats"),
+        None => bail!("Cannot find the nats cli config directory."),
+    };
+
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => selected(&dir)?,
+    };
+
+    let path = dir.join("context").join(format!("{}.json", name));
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Cannot read context '{}'", path.display()))?;
+    let context: NatsContext = serde_json::from_str(&content)
+        .with_context(|| format!("Cannot parse context '{}'", path.display()))?;
+
+    Ok(config::Profile {
+        url: set(context.url),
+        username: set(context.user),
+        password: set(context.password),
+        token: set(context.token),
+        credentials: set(context.creds),
+        nkey: set(context.nkey),
+        tls: TlsOptions {
+            ca: set(context.ca),
+            cert: set(context.cert),
+            key: set(context.key),
+            required: false,
+            first: context.tls_first,
+        },
+        ..config::Profile::default()
+    })
+}
+
+// selected reads the name of the context chosen with `nats context select`
+fn selected(dir: &Path) -> Result<String> {
+    let path = dir.join("context.txt");
+    let name = fs::read_to_string(&path)
+        .with_context(|| format!("No context selected, cannot read '{}'", path.display()))?;
+
+    match name.trim() {
+        "" => bail!("No context selected in '{}'", path.display()),
+        name => Ok(name.to_string()),
+    }
+}
+
+fn set(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.is_empty())
+}
@@ -0,0 +1,488 @@
+use crate::{
+    message::Message,
+    nats::{LiveSubscription, SharedClient},
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+// a message without its counterpart for this duration is missing on the
+// other side
+const MISSING_AFTER: Duration = Duration::from_secs(5);
+
+// entries kept, the oldest are dropped beyond it
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Clone, Copy)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    fn other(self) -> Self {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DiffSource {
+    pub session: usize,
+    pub subject: String,
+}
+
+// DiffKey pairs the messages of both sources
+#[derive(Clone)]
+pub enum DiffKey {
+    Header(String),
+    // dot separated path into a json payload, numbers index arrays
+    JsonPath(String),
+}
+
+impl DiffKey {
+    // parse reads "header:Name" or a json path with an optional "$." prefix,
+    // a bare "$" is the whole payload
+    pub fn parse(key: &str) -> Self {
+        match key.strip_prefix("header:") {
+            Some(name) => DiffKey::Header(name.trim().to_string()),
+            None if key == "$" => DiffKey::JsonPath(String::new()),
+            None => DiffKey::JsonPath(key.strip_prefix("$.").unwrap_or(key).to_string()),
+        }
+    }
+
+    fn of(&self, msg: &Message) -> Option<String> {
+        match self {
+            DiffKey::Header(name) => msg
+                .headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone()),
+            DiffKey::JsonPath(path) => {
+                let payload = serde_json::from_slice::<Value>(&msg.data).ok()?;
+                match lookup(&payload, path)? {
+                    Value::String(value) => Some(value.clone()),
+                    value => Some(value.to_string()),
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for DiffKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffKey::Header(name) => write!(f, "header:{}", name),
+            DiffKey::JsonPath(path) if path.is_empty() => write!(f, "$"),
+            DiffKey::JsonPath(path) => write!(f, "$.{}", path),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DiffConfig {
+    pub left: DiffSource,
+    pub right: DiffSource,
+    pub key: DiffKey,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EntryState {
+    Pending,
+    Matched,
+    Different,
+    MissingLeft,
+    MissingRight,
+}
+
+impl EntryState {
+    pub fn title(&self) -> &'static str {
+        match self {
+            EntryState::Pending => "pending",
+            EntryState::Matched => "matched",
+            EntryState::Different => "different",
+            EntryState::MissingLeft => "missing left",
+            EntryState::MissingRight => "missing right",
+        }
+    }
+}
+
+// Entry holds the messages of both sources with the same key
+pub struct Entry {
+    pub key: String,
+    pub left: Option<Message>,
+    pub right: Option<Message>,
+    // whether the payloads are the same, set once both messages arrived
+    matched: Option<bool>,
+}
+
+impl Entry {
+    fn new(key: String, side: Side, msg: Message) -> Self {
+        let mut entry = Self {
+            key,
+            left: None,
+            right: None,
+            matched: None,
+        };
+        *entry.slot(side) = Some(msg);
+        entry
+    }
+
+    fn slot(&mut self, side: Side) -> &mut Option<Message> {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    // pair adds the counterpart and compares the payloads
+    fn pair(&mut self, side: Side, msg: Message) {
+        *self.slot(side) = Some(msg);
+        if let (Some(left), Some(right)) = (&self.left, &self.right) {
+            self.matched = Some(same_payload(&left.data, &right.data));
+        }
+    }
+
+    pub fn state(&self) -> EntryState {
+        match (&self.left, &self.right) {
+            (Some(_), Some(_)) if self.matched == Some(true) => EntryState::Matched,
+            (Some(_), Some(_)) => EntryState::Different,
+            (Some(left), None) if left.received.elapsed() >= MISSING_AFTER => {
+                EntryState::MissingRight
+            }
+            (None, Some(right)) if right.received.elapsed() >= MISSING_AFTER => {
+                EntryState::MissingLeft
+            }
+            _ => EntryState::Pending,
+        }
+    }
+
+    // skew is how much later the right message arrived in milliseconds,
+    // negative when it came first
+    pub fn skew(&self) -> Option<f64> {
+        let (left, right) = (self.left.as_ref()?, self.right.as_ref()?);
+        Some(match right.received >= left.received {
+            true => (right.received - left.received).as_secs_f64() * 1000.0,
+            false => -(left.received - right.received).as_secs_f64() * 1000.0,
+        })
+    }
+
+    // differences lists the changed fields of json payloads, or notes that
+    // the payloads differ
+    pub fn differences(&self) -> Vec<String> {
+        let (left, right) = match (&self.left, &self.right) {
+            (Some(left), Some(right)) if self.matched == Some(false) => (left, right),
+            _ => return Vec::new(),
+        };
+
+        match (
+            serde_json::from_slice::<Value>(&left.data),
+            serde_json::from_slice::<Value>(&right.data),
+        ) {
+            (Ok(l), Ok(r)) => {
+                let mut differences = Vec::new();
+                compare(&l, &r, "$", &mut differences);
+                differences
+            }
+            _ => vec![format!(
+                "payload differs ({} vs {} bytes)",
+                left.data.len(),
+                right.data.len()
+            )],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DiffState {
+    pub entries: VecDeque<Entry>,
+    // messages without the key
+    pub unkeyed: [u64; 2],
+    // ids of the entries of a key waiting for a message of either side, in
+    // arrival order
+    waiting: HashMap<String, [VecDeque<usize>; 2]>,
+    // id of the first entry, the older ones have been dropped
+    first: usize,
+}
+
+impl DiffState {
+    // track pairs the message with the oldest entry of its key that waits for
+    // its side, a repeated key pairs in arrival order
+    fn track(&mut self, side: Side, key: &DiffKey, msg: Message) {
+        let key = match key.of(&msg) {
+            Some(key) => key,
+            None => {
+                self.unkeyed[side as usize] += 1;
+                return;
+            }
+        };
+
+        let waiting = self.waiting.entry(key.clone()).or_default();
+        match waiting[side as usize].pop_front() {
+            Some(id) => {
+                if waiting.iter().all(VecDeque::is_empty) {
+                    self.waiting.remove(&key);
+                }
+                self.entries[id - self.first].pair(side, msg);
+            }
+            None => {
+                waiting[side.other() as usize].push_back(self.first + self.entries.len());
+                self.entries.push_back(Entry::new(key, side, msg));
+                if self.entries.len() > MAX_ENTRIES {
+                    self.drop_oldest();
+                }
+            }
+        }
+    }
+
+    fn drop_oldest(&mut self) {
+        let (id, entry) = match self.entries.pop_front() {
+            Some(entry) => (self.first, entry),
+            None => return,
+        };
+        self.first += 1;
+
+        // the oldest entry is the first waiting one of its key
+        if let Some(waiting) = self.waiting.get_mut(&entry.key) {
+            for queue in waiting.iter_mut() {
+                if queue.front() == Some(&id) {
+                    queue.pop_front();
+                }
+            }
+            if waiting.iter().all(VecDeque::is_empty) {
+                self.waiting.remove(&entry.key);
+            }
+        }
+    }
+}
+
+// Diff subscribes both sources and pairs their messages by key until it is
+// stopped
+pub struct Diff {
+    subs: Vec<LiveSubscription>,
+    pub state: Arc<Mutex<DiffState>>,
+    pub config: DiffConfig,
+}
+
+impl Diff {
+    // start fails when a session is not connected
+    pub fn start(config: DiffConfig, left: SharedClient, right: SharedClient) -> Result<Self> {
+        let state = Arc::new(Mutex::new(DiffState::default()));
+        let mut subs = Vec::<LiveSubscription>::new();
+
+        for (side, source, nc) in [
+            (Side::Left, &config.left, left),
+            (Side::Right, &config.right, right),
+        ] {
+            let tracked = state.clone();
+            let key = config.key.clone();
+            match LiveSubscription::start(nc, source.subject.clone(), move |msg| {
+                tracked.lock().unwrap().track(side, &key, msg.into())
+            }) {
+                Ok(sub) => subs.push(sub),
+                Err(err) => {
+                    subs.iter().for_each(LiveSubscription::stop);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(Self {
+            subs,
+            state,
+            config,
+        })
+    }
+
+    pub fn stop(&self) {
+        self.subs.iter().for_each(LiveSubscription::stop);
+    }
+}
+
+// same_payload compares json payloads by value, so key order and whitespace
+// don't matter, and any other payload byte by byte
+fn same_payload(left: &[u8], right: &[u8]) -> bool {
+    match (
+        serde_json::from_slice::<Value>(left),
+        serde_json::from_slice::<Value>(right),
+    ) {
+        (Ok(l), Ok(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|p| !p.is_empty())
+        .try_fold(value, |value, p| match value {
+            Value::Array(items) => items.get(p.parse::<usize>().ok()?),
+            value => value.get(p),
+        })
+}
+
+// compare collects the paths whose values differ
+fn compare(left: &Value, right: &Value, path: &str, differences: &mut Vec<String>) {
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            let mut keys = l.keys().chain(r.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = format!("{}.{}", path, key);
+                match (l.get(key), r.get(key)) {
+                    (Some(l), Some(r)) => compare(l, r, &path, differences),
+                    (Some(l), None) => differences.push(format!("{}: {} -> missing", path, l)),
+                    (None, Some(r)) => differences.push(format!("{}: missing -> {}", path, r)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(l), Value::Array(r)) if l.len() == r.len() => {
+            for (i, (l, r)) in l.iter().zip(r).enumerate() {
+                compare(l, r, &format!("{}.{}", path, i), differences);
+            }
+        }
+        (l, r) if l != r => differences.push(format!("{}: {} -> {}", path, l, r)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(data: &str) -> Message {
+        Message::new("diff".to_string(), None, Vec::new(), data.as_bytes())
+    }
+
+    fn entry(left: &str, right: &str) -> Entry {
+        let mut entry = Entry::new("1".to_string(), Side::Left, message(left));
+        entry.pair(Side::Right, message(right));
+        entry
+    }
+
+    fn payloads(state: &DiffState) -> Vec<(&str, &str)> {
+        fn payload(m: &Option<Message>) -> &str {
+            m.as_ref().map_or("", |m| m.payload.as_str())
+        }
+        state
+            .entries
+            .iter()
+            .map(|e| (payload(&e.left), payload(&e.right)))
+            .collect()
+    }
+
+    fn path(key: &str) -> String {
+        match DiffKey::parse(key) {
+            DiffKey::JsonPath(path) => path,
+            DiffKey::Header(name) => panic!("header key {}", name),
+        }
+    }
+
+    #[test]
+    fn json_with_other_order_matches() {
+        let entry = entry(r#"{"id": 1, "name": "a"}"#, r#"{"name":"a","id":1}"#);
+        assert!(entry.state() == EntryState::Matched);
+        assert!(entry.differences().is_empty());
+    }
+
+    #[test]
+    fn json_with_other_values_differs() {
+        let entry = entry(r#"{"id": 1, "name": "a"}"#, r#"{"id": 1, "name": "b"}"#);
+        assert!(entry.state() == EntryState::Different);
+        assert_eq!(entry.differences(), vec![r#"$.name: "a" -> "b""#]);
+    }
+
+    #[test]
+    fn text_is_compared_by_bytes() {
+        assert!(entry("a b", "a b").state() == EntryState::Matched);
+        assert!(entry("a b", "a  b").state() == EntryState::Different);
+        assert!(entry("{}", "{ ").state() == EntryState::Different);
+    }
+
+    #[test]
+    fn pairs_repeated_keys_in_arrival_order() {
+        let key = DiffKey::parse("header:Id");
+        let mut state = DiffState::default();
+        let keyed = |id: &str, data: &str| {
+            let mut msg = message(data);
+            msg.headers = vec![("Id".to_string(), id.to_string())];
+            msg
+        };
+        state.track(Side::Left, &key, keyed("1", "l1"));
+        state.track(Side::Left, &key, keyed("1", "l2"));
+        state.track(Side::Right, &key, keyed("1", "r1"));
+        state.track(Side::Right, &key, keyed("2", "r2"));
+        state.track(Side::Right, &key, keyed("1", "r3"));
+        state.track(Side::Right, &key, keyed("1", "r4"));
+        state.track(Side::Left, &key, keyed("1", "l5"));
+        state.track(Side::Left, &key, message("no key"));
+
+        assert_eq!(
+            payloads(&state),
+            [("l1", "r1"), ("l2", "r3"), ("", "r2"), ("l5", "r4")]
+        );
+        assert!(state.waiting.contains_key("2"));
+        assert_eq!(state.waiting.len(), 1);
+        assert_eq!(state.unkeyed, [1, 0]);
+    }
+
+    #[test]
+    fn drops_oldest_entries() {
+        let key = DiffKey::parse("$");
+        let mut state = DiffState::default();
+        for i in 0..=MAX_ENTRIES {
+            state.track(Side::Left, &key, message(&i.to_string()));
+        }
+        assert_eq!(state.entries.len(), MAX_ENTRIES);
+        assert_eq!(state.entries[0].key, "1");
+        assert!(!state.waiting.contains_key("0"));
+
+        state.track(Side::Right, &key, message("1"));
+        assert!(state.entries[0].state() == EntryState::Matched);
+
+        state.track(Side::Right, &key, message("0"));
+        assert_eq!(state.entries.len(), MAX_ENTRIES);
+        assert_eq!(state.entries[0].key, "2");
+        assert_eq!(state.entries.back().unwrap().key, "0");
+        assert_eq!(state.waiting.len(), MAX_ENTRIES);
+    }
+
+    #[test]
+    fn parses_keys() {
+        assert!(matches!(DiffKey::parse("header:Id"), DiffKey::Header(name) if name == "Id"));
+        assert_eq!(path("$.order.id"), "order.id");
+        assert_eq!(path("order.id"), "order.id");
+        assert_eq!(path("$"), "");
+        assert_eq!(path("$."), "");
+        assert_eq!(DiffKey::parse("$").to_string(), "$");
+        assert_eq!(DiffKey::parse("id").to_string(), "$.id");
+    }
+
+    #[test]
+    fn looks_up_paths() {
+        let value = serde_json::json!({"order": {"items": [{"id": 7}]}, "$": 1});
+        assert_eq!(
+            lookup(&value, "order.items.0.id"),
+            Some(&serde_json::json!(7))
+        );
+        assert_eq!(lookup(&value, ""), Some(&value));
+        assert_eq!(lookup(&value, "order.items.1"), None);
+        assert_eq!(lookup(&value, "order.items.x"), None);
+        assert_eq!(lookup(&value, "missing"), None);
+    }
+
+    #[test]
+    fn whole_payload_key() {
+        let key = DiffKey::parse("$");
+        assert_eq!(key.of(&message("42")), Some("42".to_string()));
+        assert_eq!(key.of(&message(r#""a""#)), Some("a".to_string()));
+        assert_eq!(key.of(&message("not json")), None);
+    }
+}
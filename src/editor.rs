@@ -0,0 +1,392 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    borrow::Cow,
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    process::Command,
+};
+use unicode_width::UnicodeWidthStr;
+
+// cells a tab takes on screen
+const TAB_WIDTH: usize = 4;
+
+// TextArea is a multi-line text input with a cursor
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+    // json parse error with its line, shown until the next edit
+    error: Option<(usize, String)>,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+            error: None,
+        }
+    }
+}
+
+impl TextArea {
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    // set_text replaces the content and moves the cursor to its end
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.lines().map(str::to_string).collect();
+        if self.lines.is_empty() || text.ends_with('\n') {
+            self.lines.push(String::new());
+        }
+        self.row = self.lines.len() - 1;
+        self.col = self.lines[self.row].chars().count();
+        self.error = None;
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    // column of the cursor in terminal cells
+    pub fn cursor_width(&self) -> usize {
+        let line = &self.lines[self.row];
+        expand_tabs(&line[..byte_index(line, self.col)]).width()
+    }
+
+    pub fn error(&self) -> Option<&(usize, String)> {
+        self.error.as_ref()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let line = &mut self.lines[self.row];
+        line.insert(byte_index(line, self.col), c);
+        self.col += 1;
+        self.error = None;
+    }
+
+    pub fn newline(&mut self) {
+        let line = &mut self.lines[self.row];
+        let rest = line.split_off(byte_index(line, self.col));
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+        self.error = None;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let line = &mut self.lines[self.row];
+            line.remove(byte_index(line, self.col));
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.lines[self.row].chars().count();
+            self.lines[self.row].push_str(&line);
+        }
+        self.error = None;
+    }
+
+    pub fn delete(&mut self) {
+        let len = self.lines[self.row].chars().count();
+        if self.col < len {
+            let line = &mut self.lines[self.row];
+            line.remove(byte_index(line, self.col));
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+        self.error = None;
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.lines[self.row].chars().count();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.lines[self.row].chars().count() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.clamp_col();
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.clamp_col();
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = self.lines[self.row].chars().count();
+    }
+
+    // format_json pretty prints the content if it is valid json, otherwise
+    // keeps the error and moves the cursor to it
+    pub fn format_json(&mut self) {
+        match serde_json::from_str::<serde_json::Value>(&self.text()) {
+            Ok(value) => {
+                let pretty = serde_json::to_string_pretty(&value).unwrap_or_default();
+                self.set_text(&pretty);
+            }
+            Err(err) => {
+                let row = err.line().saturating_sub(1).min(self.lines.len() - 1);
+                self.row = row;
+                self.col = err.column().saturating_sub(1);
+                self.clamp_col();
+                self.error = Some((row, err.to_string()));
+            }
+        }
+    }
+
+    fn clamp_col(&mut self) {
+        self.col = self.col.min(self.lines[self.row].chars().count());
+    }
+}
+
+// expand_tabs replaces tabs with spaces for drawing a line
+pub fn expand_tabs(line: &str) -> Cow<'_, str> {
+    match line.contains('\t') {
+        true => Cow::Owned(line.replace('\t', &" ".repeat(TAB_WIDTH))),
+        false => Cow::Borrowed(line),
+    }
+}
+
+fn byte_index(line: &str, col: usize) -> usize {
+    line.char_indices()
+        .nth(col)
+        .map(|(i, _)| i)
+        .unwrap_or_else(|| line.len())
+}
+
+// edit_external opens $VISUAL or $EDITOR on a temp file seeded with the text
+// and returns the edited text, the terminal must be released by the caller
+pub fn edit_external(text: &str) -> Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut args = editor.split_whitespace();
+    let program = match args.next() {
+        Some(program) => program,
+        None => bail!("$EDITOR is empty."),
+    };
+
+    let path = temp_file(text)?;
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Cannot start editor '{}'", editor));
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    if !status?.success() {
+        bail!("Editor '{}' exited with an error.", editor);
+    }
+
+    let mut edited = edited.with_context(|| format!("Cannot read '{}'", path.display()))?;
+    // editors terminate the last line
+    if edited.ends_with('\n') {
+        edited.pop();
+    }
+
+    Ok(edited)
+}
+
+// temp_file writes the text to a new file only the user can read, a random
+// name and create_new keep it from following a planted file or symlink
+fn temp_file(text: &str) -> Result<PathBuf> {
+    let path = env::temp_dir().join(format!(
+        "nats-spy-{}-{:016x}.txt",
+        std::process::id(),
+        rand::random::<u64>()
+    ));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("Cannot create '{}'", path.display()))?;
+    if let Err(err) = file.write_all(text.as_bytes()) {
+        let _ = fs::remove_file(&path);
+        return Err(err).with_context(|| format!("Cannot write '{}'", path.display()));
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_area(text: &str) -> TextArea {
+        let mut area = TextArea::default();
+        area.set_text(text);
+        area
+    }
+
+    fn cursor(area: &TextArea) -> (usize, usize) {
+        (area.row, area.col)
+    }
+
+    #[test]
+    fn set_text_keeps_lines() {
+        for text in ["", "a", "a\nb", "a\n", "\n\n", "{\n\t\"a\": 1\n}"] {
+            assert_eq!(text_area(text).text(), text);
+        }
+        assert_eq!(text_area("a\r\nb").text(), "a\nb");
+
+        let area = text_area("ab\ncde");
+        assert_eq!(area.lines(), ["ab", "cde"]);
+        assert_eq!(cursor(&area), (1, 3));
+    }
+
+    #[test]
+    fn edits_multibyte_text() {
+        let mut area = text_area("aé");
+        area.left();
+        area.insert('ü');
+        assert_eq!(area.text(), "aüé");
+        area.backspace();
+        area.delete();
+        assert_eq!(area.text(), "a");
+        area.insert('日');
+        assert_eq!(area.cursor_width(), 3);
+    }
+
+    #[test]
+    fn splits_and_joins_lines() {
+        let mut area = text_area("abcd");
+        area.left();
+        area.left();
+        area.newline();
+        assert_eq!(area.lines(), ["ab", "cd"]);
+        assert_eq!(cursor(&area), (1, 0));
+
+        area.backspace();
+        assert_eq!(area.lines(), ["abcd"]);
+        assert_eq!(cursor(&area), (0, 2));
+
+        area.end();
+        area.newline();
+        area.up();
+        area.end();
+        area.delete();
+        assert_eq!(area.lines(), ["abcd"]);
+    }
+
+    #[test]
+    fn keeps_cursor_inside_text() {
+        let mut area = text_area("long line\nab");
+        area.up();
+        area.end();
+        area.down();
+        assert_eq!(cursor(&area), (1, 2));
+        area.right();
+        area.down();
+        assert_eq!(cursor(&area), (1, 2));
+
+        area.home();
+        area.left();
+        assert_eq!(cursor(&area), (0, 9));
+        area.right();
+        assert_eq!(cursor(&area), (1, 0));
+
+        let mut area = TextArea::default();
+        area.backspace();
+        area.delete();
+        area.left();
+        area.up();
+        assert_eq!(area.lines(), [""]);
+        assert_eq!(cursor(&area), (0, 0));
+    }
+
+    #[test]
+    fn expands_tabs() {
+        assert_eq!(expand_tabs("a\tb"), "a    b");
+        assert!(matches!(expand_tabs("ab"), Cow::Borrowed("ab")));
+
+        let mut area = TextArea::default();
+        area.insert('\t');
+        area.insert('x');
+        assert_eq!(area.text(), "\tx");
+        assert_eq!(area.cursor_width(), TAB_WIDTH + 1);
+    }
+
+    #[test]
+    fn formats_json() {
+        let mut area = text_area(r#"{"a":[1,2]}"#);
+        area.format_json();
+        assert_eq!(area.text(), "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+        assert!(area.error().is_none());
+    }
+
+    #[test]
+    fn keeps_invalid_json_with_its_error() {
+        let mut area = text_area("{\n  \"a\": x\n}");
+        area.format_json();
+        assert_eq!(area.text(), "{\n  \"a\": x\n}");
+        assert_eq!(area.error().map(|(row, _)| *row), Some(1));
+        assert_eq!(area.row(), 1);
+
+        area.insert('1');
+        assert!(area.error().is_none());
+
+        let mut area = text_area("{\"a\": ");
+        area.format_json();
+        assert!(area.error().is_some());
+        assert!(area.row() < area.lines().len());
+    }
+
+    #[test]
+    fn temp_file_is_new_and_private() {
+        let first = temp_file("secret").unwrap();
+        let second = temp_file("secret").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "secret");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+}
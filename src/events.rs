@@ -1,151 +1,545 @@
-use crate::nats::NatsClient;
+use crate::{
+    bench::{self, BenchConfig, BenchResult},
+    bridge::{Bridge, BridgeConfig},
+    diff::{Diff, DiffConfig},
+    load::{LoadConfig, LoadGenerator},
+    message::Message,
+    monitor::{ConnInfo, Connz, MonitorClient, ServerApiResponse, ServerStats},
+    nats::{NatsClient, SharedClient},
+    responder::{Responder, Rule},
+    services::{self, Endpoint, Instance},
+    status::ConnectionState,
+    system::{SystemEvent, SYSTEM_SUBJECTS},
+};
 use anyhow::Result;
-use crossterm::event::{read, Event};
+use chrono::Local;
+use crossterm::event::{poll, read, Event};
 use log::{error, info};
+use nats::Subscription;
 use std::{
     sync::{
+        atomic::{AtomicBool, Ordering},
         mpsc::{channel, Receiver, RecvError, Sender},
-        Arc, Mutex,
+        Arc, Mutex, MutexGuard,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+const KEYBOARD_POLL: Duration = Duration::from_millis(50);
+const RTT_INTERVAL: Duration = Duration::from_secs(5);
+const CONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const CONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 pub enum InputEvent {
     Input(Event),
-    Messages(String, String),
+    Messages(Message),
+    Monitor(Box<ServerStats>),
+    Connections(Vec<ConnInfo>),
+    System(Box<SystemEvent>),
+    Services(Vec<Instance>),
+    // request schema of the endpoint with the subject
+    Schema(String, String),
+    Bench(Arc<BenchResult>),
     Tick,
 }
 
+// Session is one server connection with its own subscriptions
+#[derive(Clone)]
+pub struct Session {
+    pub name: String,
+    pub nats_client: NatsClient,
+    pub subjects: Vec<String>,
+    pub system: bool,
+}
+
 pub struct Events {
     rx: Receiver<InputEvent>,
     tx: Sender<InputEvent>,
-    nats_client: Arc<Mutex<NatsClient>>,
+    sessions: Vec<Session>,
+    nats_clients: Vec<SharedClient>,
+    // session used for publishing, requests and the other tools
+    active: usize,
+    monitor: Option<MonitorClient>,
+    responder: Responder,
+    // keyboard reading stops while an external program owns the terminal
+    keyboard_paused: Arc<AtomicBool>,
 }
 
 impl Events {
     pub fn new(
-        nats_url: String,
-        subject: String,
-        username: Option<String>,
-        password: Option<String>,
-        token: Option<String>,
-        credentials: Option<String>,
+        sessions: Vec<Session>,
+        monitor: Option<MonitorClient>,
+        responder: Responder,
     ) -> Events {
         let (tx, rx) = channel();
 
         // listen keyboard events
         let tx_keyboard = tx.clone();
+        let keyboard_paused = Arc::new(AtomicBool::new(false));
+        let paused = keyboard_paused.clone();
         thread::spawn(move || loop {
-            if let Ok(key) = read() {
-                if let Err(err) = tx_keyboard.send(InputEvent::Input(key)) {
+            if paused.load(Ordering::SeqCst) {
+                thread::sleep(KEYBOARD_POLL);
+                continue;
+            }
+
+            if let Ok(true) = poll(KEYBOARD_POLL) {
+                if let Ok(key) = read() {
+                    if let Err(err) = tx_keyboard.send(InputEvent::Input(key)) {
+                        eprintln!("{}", err);
+                        return;
+                    }
+                }
+            }
+        });
+
+        // start ticker event and poll monitoring endpoint on its interval
+        let tx_tick = tx.clone();
+        let monitor_tick = monitor.clone();
+        thread::spawn(move || {
+            let monitor = monitor_tick;
+            let mut last_poll: Option<Instant> = None;
+            loop {
+                if let Err(err) = tx_tick.send(InputEvent::Tick) {
                     eprintln!("{}", err);
                     return;
                 }
+
+                if let Some(monitor) = &monitor {
+                    if last_poll.is_none_or(|t| t.elapsed() >= monitor.interval()) {
+                        last_poll = Some(Instant::now());
+                        Self::poll_monitor(monitor.clone(), tx_tick.clone());
+                    }
+                }
+
+                thread::sleep(Duration::from_millis(200));
             }
         });
 
-        // start ticker event
-        let tx_tick = tx.clone();
+        let nats_clients = sessions
+            .iter()
+            .map(|s| Arc::new(Mutex::new(s.nats_client.clone())))
+            .collect::<Vec<_>>();
+
+        // measure the round trip times for the status bar, checking every
+        // second until connected
+        let nc_rtt = nats_clients.clone();
         thread::spawn(move || loop {
-            if let Err(err) = tx_tick.send(InputEvent::Tick) {
-                eprintln!("{}", err);
-                return;
+            let mut measured = true;
+            for nc in &nc_rtt {
+                let nc = nc.lock().unwrap().clone();
+                let connected = nc.is_connected()
+                    && nc.status().lock().unwrap().state == ConnectionState::Connected;
+                measured &= connected && nc.rtt().is_ok();
+            }
+            match measured {
+                true => thread::sleep(RTT_INTERVAL),
+                false => thread::sleep(Duration::from_secs(1)),
             }
-            thread::sleep(Duration::from_millis(200));
         });
 
-        let nats_client = Arc::new(Mutex::new(NatsClient::new(
-            nats_url,
-            username,
-            password,
-            token,
-            credentials,
-        )));
-
-        // start nats client and listen
-        let nc = nats_client.clone();
-        let tx_message = tx.clone();
+        let events = Events {
+            rx,
+            tx,
+            sessions,
+            nats_clients,
+            active: 0,
+            monitor,
+            responder,
+            keyboard_paused,
+        };
+
+        // start nats clients and listen
+        for index in 0..events.sessions.len() {
+            events.connect(index);
+        }
+
+        events
+    }
+
+    // connect the session until it succeeds, waiting longer after every
+    // failed attempt, and subscribe in the background
+    fn connect(&self, index: usize) {
+        let session = self.sessions[index].clone();
+        let nats_client = self.nats_clients[index].clone();
+        // mock responders answer on the first session
+        let responder = Some(self.responder.clone()).filter(|_| index == 0);
+        let tx = self.tx.clone();
+        let tag = match self.sessions.len() {
+            1 => String::new(),
+            _ => format!("[{}] ", session.name),
+        };
+
         thread::spawn(move || {
-            info!("Trying to connect NATS Server...");
+            let status = session.nats_client.status();
+            // connect a copy, the session keeps serving the ui meanwhile
+            let mut client = nats_client.lock().unwrap().clone();
+            let mut backoff = CONNECT_BACKOFF;
+            loop {
+                info!("{}Trying to connect NATS Server...", tag);
+                match client.connect() {
+                    Ok(_) => break,
+                    Err(err) => error!(
+                        "{}Cannot connect. {} Retrying in {}s.",
+                        tag,
+                        err,
+                        backoff.as_secs()
+                    ),
+                }
 
-            // connect nats server
-            let mut nc = nc.lock().unwrap();
-            match nc.connect() {
-                Ok(_) => info!("Connected to NATS Server."),
-                Err(err) => {
-                    error!("Cannot connect. {}", err);
-                    return;
+                {
+                    let mut status = status.lock().unwrap();
+                    status.state = ConnectionState::Connecting;
+                    status.connect_attempts += 1;
+                    status.retry_at = Some(Instant::now() + backoff);
                 }
+                // wait in steps, the retry can be moved forward with reconnect
+                while status
+                    .lock()
+                    .unwrap()
+                    .retry_at
+                    .is_some_and(|at| at > Instant::now())
+                {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                backoff = (backoff * 2).min(CONNECT_BACKOFF_MAX);
             }
+            {
+                let mut status = status.lock().unwrap();
+                status.connect_attempts = 0;
+                status.retry_at = None;
+            }
+            info!("{}Connected to NATS Server.", tag);
 
-            // subscribe subject
-            let sub = match nc.subscribe(subject) {
-                Ok(sub) => sub,
-                Err(err) => {
-                    error!("{}", err);
+            let mut nc = nats_client.lock().unwrap();
+            *nc = client;
+
+            // subscribe subjects
+            for subject in session.subjects {
+                match nc.subscribe(subject) {
+                    Ok(sub) => Self::listen_messages(sub, index, tx.clone()),
+                    Err(err) => error!("{}{}", tag, err),
+                }
+            }
+
+            // subscribe system account events
+            if session.system {
+                for subject in SYSTEM_SUBJECTS.iter() {
+                    match nc.subscribe(subject.to_string()) {
+                        Ok(sub) => Self::listen_system(sub, tx.clone()),
+                        Err(err) => error!("{}{}", tag, err),
+                    }
+                }
+            }
+
+            // answer requests of the mock responder rules
+            if let Some(responder) = responder {
+                responder.start(&nc);
+            }
+        });
+    }
+
+    // reconnect connects the active session again after the client gave up
+    // reconnecting, or retries connecting right away
+    pub fn reconnect(&self) {
+        let status = self.sessions[self.active].nats_client.status();
+        let mut status = status.lock().unwrap();
+        if status.retry_at.is_some() {
+            status.retry_at = Some(Instant::now());
+            return;
+        }
+        if status.state != ConnectionState::Closed {
+            info!("Connection is not closed.");
+            return;
+        }
+        drop(status);
+
+        self.connect(self.active);
+    }
+
+    // select the session used for publishing, requests and the other tools
+    pub fn select_session(&mut self, index: usize) {
+        if index < self.sessions.len() {
+            self.active = index;
+        }
+    }
+
+    fn nats_client(&self) -> MutexGuard<'_, NatsClient> {
+        self.nats_clients[self.active].lock().unwrap()
+    }
+
+    // listen new messages of the subscription in the background
+    fn listen_messages(sub: Subscription, session: usize, tx: Sender<InputEvent>) {
+        thread::spawn(move || {
+            for msg in sub.messages() {
+                if msg.subject.starts_with(bench::SUBJECT_PREFIX) {
+                    continue;
+                }
+                let mut message = Message::from(msg);
+                message.session = session;
+                if tx.send(InputEvent::Messages(message)).is_err() {
                     return;
                 }
-            };
-            drop(nc);
+            }
+        });
+    }
 
-            // listen new messages
+    // decode system events of the subscription in the background
+    fn listen_system(sub: Subscription, tx: Sender<InputEvent>) {
+        thread::spawn(move || {
             for msg in sub.messages() {
-                tx_message
-                    .send(InputEvent::Messages(
-                        msg.subject,
-                        std::str::from_utf8(&msg.data).unwrap().to_string(),
-                    ))
-                    .unwrap()
+                let event = SystemEvent::decode(
+                    Local::now().format("%H:%M:%S").to_string(),
+                    msg.subject,
+                    String::from_utf8_lossy(&msg.data).to_string(),
+                );
+                if tx.send(InputEvent::System(Box::new(event))).is_err() {
+                    return;
+                }
             }
         });
+    }
 
-        Events {
-            rx,
-            tx,
-            nats_client,
+    // poll monitoring endpoints without blocking the ticker
+    fn poll_monitor(monitor: MonitorClient, tx: Sender<InputEvent>) {
+        thread::spawn(move || match monitor.poll() {
+            Ok(stats) => {
+                let _ = tx.send(InputEvent::Monitor(Box::new(stats)));
+            }
+            Err(err) => error!("{}", err),
+        });
+    }
+
+    // poll monitoring endpoints now instead of waiting for the interval
+    pub fn refresh_monitor(&self) {
+        if let Some(monitor) = &self.monitor {
+            Self::poll_monitor(monitor.clone(), self.tx.clone());
         }
     }
 
+    // pause_keyboard stops reading the terminal until resume_keyboard is called
+    pub fn pause_keyboard(&self) {
+        self.keyboard_paused.store(true, Ordering::SeqCst);
+        // let a pending poll of the keyboard thread time out
+        thread::sleep(KEYBOARD_POLL * 2);
+    }
+
+    pub fn resume_keyboard(&self) {
+        self.keyboard_paused.store(false, Ordering::SeqCst);
+    }
+
     pub fn next(&self) -> Result<InputEvent, RecvError> {
         self.rx.recv()
     }
 
-    pub fn publish(&self, sub: String, msg: String) {
+    pub fn publish_with_headers(&self, sub: String, headers: &[(String, String)], data: &[u8]) {
+        self.publish_to(self.active, sub, headers, data)
+    }
+
+    // publish_to sends the message on the given session, such as a reply on
+    // the session its request was received on
+    pub fn publish_to(
+        &self,
+        session: usize,
+        sub: String,
+        headers: &[(String, String)],
+        data: &[u8],
+    ) {
         if sub.is_empty() {
             error!("Subject is empty!");
             return;
         }
 
-        match self.nats_client.lock().unwrap().publish(sub.clone(), msg) {
+        match self.nats_clients[session]
+            .lock()
+            .unwrap()
+            .publish_with_headers(sub.clone(), None, headers, data)
+        {
             Ok(_) => info!("Message send to subject '{}'", sub.clone()),
             Err(err) => error!("{}", err),
         }
     }
 
-    pub fn request(&self, sub: String, msg: String) {
+    pub fn request(&self, sub: String, msg: Vec<u8>) {
         if sub.is_empty() {
             error!("Subject is empty!");
             return;
         }
 
         info!("Subject '{}' requested.", sub.clone());
-        match self.nats_client.lock().unwrap().request(sub, msg) {
-            Ok(resp) => self
-                .tx
-                .send(InputEvent::Messages(
-                    resp.subject,
-                    std::str::from_utf8(&resp.data).unwrap().to_string(),
-                ))
-                .unwrap(),
+        match self.nats_client().request(sub, msg) {
+            Ok(resp) => {
+                let mut message = Message::from(resp);
+                message.session = self.active;
+                self.tx.send(InputEvent::Messages(message)).unwrap()
+            }
             Err(err) => {
                 error!("{}", err)
             }
         }
     }
 
+    // request_connz asks every server in the cluster for its connections
+    // using the system account
+    pub fn request_connz(&self) {
+        let nc = self.nats_client().clone();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let responses = match nc.request_multi(
+                "$SYS.REQ.SERVER.PING.CONNZ".to_string(),
+                r#"{"subscriptions": true}"#.to_string(),
+            ) {
+                Ok(responses) => responses,
+                Err(err) => {
+                    error!("{}", err);
+                    return;
+                }
+            };
+
+            if responses.is_empty() {
+                error!("No response for connections. Connect with the system account or use --monitor-url.");
+                return;
+            }
+
+            let mut connections = Vec::new();
+            for resp in responses {
+                match serde_json::from_slice::<ServerApiResponse<Connz>>(&resp.data) {
+                    Ok(ServerApiResponse { data: Some(connz) }) => {
+                        connections.extend(connz.connections)
+                    }
+                    Ok(_) => error!("Connections response has no data."),
+                    Err(err) => error!("Cannot parse connections. {}", err),
+                }
+            }
+
+            let _ = tx.send(InputEvent::Connections(connections));
+        });
+    }
+
+    // start_load publishes repeatedly from background threads
+    pub fn start_load(&self, config: LoadConfig) -> LoadGenerator {
+        match config.count {
+            Some(count) => info!("Publishing {} messages to '{}'.", count, config.subject),
+            None => info!("Publishing to '{}' until stopped.", config.subject),
+        }
+        LoadGenerator::start(config, self.nats_clients[self.active].clone())
+    }
+
+    // start_bridge forwards the messages of a subject between two sessions
+    pub fn start_bridge(&self, config: BridgeConfig) -> Option<Bridge> {
+        let from = self.nats_clients[config.from].clone();
+        let to = self.nats_clients[config.to].clone();
+        let names = (
+            self.sessions[config.from].name.clone(),
+            self.sessions[config.to].name.clone(),
+        );
+        match Bridge::start(config, from, to) {
+            Ok(bridge) => {
+                info!(
+                    "Forwarding '{}' from {} to {}.",
+                    bridge.config.subject, names.0, names.1
+                );
+                Some(bridge)
+            }
+            Err(err) => {
+                error!("Cannot start bridge. {}", err);
+                None
+            }
+        }
+    }
+
+    // start_diff pairs the messages of two sources
+    pub fn start_diff(&self, config: DiffConfig) -> Option<Diff> {
+        let left = self.nats_clients[config.left.session].clone();
+        let right = self.nats_clients[config.right.session].clone();
+        match Diff::start(config, left, right) {
+            Ok(diff) => {
+                info!("Comparing messages by {}.", diff.config.key);
+                Some(diff)
+            }
+            Err(err) => {
+                error!("Cannot start comparison. {}", err);
+                None
+            }
+        }
+    }
+
+    // bench runs the benchmark in the background
+    pub fn bench(&self, config: BenchConfig) {
+        let nc = self.nats_client().clone();
+        let tx = self.tx.clone();
+        info!("Running {} benchmark...", config.mode);
+        thread::spawn(move || match bench::run(config, &nc) {
+            Ok(result) => {
+                info!("{} benchmark finished.", config.mode);
+                let _ = tx.send(InputEvent::Bench(Arc::new(result)));
+            }
+            Err(err) => error!("{}", err),
+        });
+    }
+
+    // discover_services collects the nats micro services answering
+    // $SRV.PING, $SRV.INFO and $SRV.STATS in the background
+    pub fn discover_services(&self) {
+        let nc = self.nats_client().clone();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let request = |subject: &str| -> Result<Vec<Vec<u8>>> {
+                Ok(nc
+                    .request_multi(subject.to_string(), String::new())?
+                    .into_iter()
+                    .map(|msg| msg.data)
+                    .collect())
+            };
+
+            let instances = request(services::PING_SUBJECT).and_then(|ping| {
+                let info = request(services::INFO_SUBJECT)?;
+                let stats = request(services::STATS_SUBJECT)?;
+                Ok(services::discover(&ping, &info, &stats))
+            });
+
+            match instances {
+                Ok(instances) => {
+                    info!("Discovered {} service instances.", instances.len());
+                    let _ = tx.send(InputEvent::Services(instances));
+                }
+                Err(err) => error!("Cannot discover services. {}", err),
+            }
+        });
+    }
+
+    // request_schema asks the service instance for the request schema of its
+    // endpoint
+    pub fn request_schema(&self, instance: &Instance, endpoint: &Endpoint) {
+        let nc = self.nats_client().clone();
+        let tx = self.tx.clone();
+        let subject = format!(
+            "{}.{}.{}",
+            services::SCHEMA_SUBJECT,
+            instance.service,
+            instance.id
+        );
+        let (name, endpoint) = (endpoint.info.name.clone(), endpoint.info.subject.clone());
+        thread::spawn(move || match nc.request(subject, "") {
+            Ok(resp) => {
+                if let Some(schema) = services::request_schema(&resp.data, &name) {
+                    let _ = tx.send(InputEvent::Schema(endpoint, schema));
+                }
+            }
+            Err(err) => error!("Cannot read the schema of '{}'. {}", endpoint, err),
+        });
+    }
+
+    pub fn add_responder(&self, rule: Rule) {
+        self.responder
+            .add(rule, &self.nats_clients[0].lock().unwrap())
+    }
+
     pub fn drain(&mut self) {
-        self.nats_client.lock().unwrap().drain()
+        for nc in &self.nats_clients {
+            nc.lock().unwrap().drain()
+        }
     }
 }
@@ -0,0 +1,258 @@
+use serde::Deserialize;
+
+const ADVISORY_PREFIX: &str = "$JS.EVENT.ADVISORY.";
+const METRIC_PREFIX: &str = "$JS.EVENT.METRIC.";
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ClientInfo {
+    acc: String,
+    name: String,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Replica {
+    name: String,
+    current: bool,
+}
+
+// union of the fields used by the jetstream advisory and metric schemas
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Event {
+    #[serde(rename = "type")]
+    kind: String,
+    stream: String,
+    consumer: String,
+    stream_seq: u64,
+    deliveries: u64,
+    reason: String,
+    leader: String,
+    replicas: Vec<Replica>,
+    action: String,
+    ack_time: u64,
+    subject: String,
+    client: ClientInfo,
+}
+
+// is_advisory checks whether the subject carries a jetstream advisory or metric
+pub fn is_advisory(subject: &str) -> bool {
+    subject.starts_with(ADVISORY_PREFIX) || subject.starts_with(METRIC_PREFIX)
+}
+
+// summarize renders a human readable line for jetstream advisories and metrics
+pub fn summarize(subject: &str, payload: &str) -> Option<String> {
+    if !is_advisory(subject) {
+        return None;
+    }
+
+    let e = match serde_json::from_str::<Event>(payload) {
+        Ok(e) => e,
+        Err(_) => return Some(format!("unreadable advisory on {}", subject)),
+    };
+
+    let schema = e
+        .kind
+        .trim_start_matches("io.nats.jetstream.advisory.v1.")
+        .trim_start_matches("io.nats.jetstream.metric.v1.");
+
+    let summary = match schema {
+        "max_deliver" => format!(
+            "consumer {} > {} reached max deliveries ({}) for stream seq {}",
+            e.stream, e.consumer, e.deliveries, e.stream_seq
+        ),
+        "nak" => format!(
+            "consumer {} > {} NAKed stream seq {} after {} deliveries",
+            e.stream, e.consumer, e.stream_seq, e.deliveries
+        ),
+        "terminated" => format!(
+            "consumer {} > {} terminated stream seq {} after {} deliveries{}",
+            e.stream,
+            e.consumer,
+            e.stream_seq,
+            e.deliveries,
+            reason(&e.reason)
+        ),
+        "stream_leader_elected" => format!(
+            "stream {} elected leader {} ({})",
+            e.stream,
+            e.leader,
+            replicas(&e.replicas)
+        ),
+        "consumer_leader_elected" => format!(
+            "consumer {} > {} elected leader {} ({})",
+            e.stream,
+            e.consumer,
+            e.leader,
+            replicas(&e.replicas)
+        ),
+        "stream_quorum_lost" => format!(
+            "stream {} lost quorum ({})",
+            e.stream,
+            replicas(&e.replicas)
+        ),
+        "consumer_quorum_lost" => format!(
+            "consumer {} > {} lost quorum ({})",
+            e.stream,
+            e.consumer,
+            replicas(&e.replicas)
+        ),
+        "stream_action" => format!("stream {} {}", e.stream, e.action),
+        "consumer_action" => format!("consumer {} > {} {}", e.stream, e.consumer, e.action),
+        "api_audit" => format!(
+            "api {} called by {} ({})",
+            e.subject, e.client.name, e.client.acc
+        ),
+        "consumer_ack" => format!(
+            "consumer {} > {} acked stream seq {} in {:.3} ms after {} deliveries",
+            e.stream,
+            e.consumer,
+            e.stream_seq,
+            e.ack_time as f64 / 1_000_000.0,
+            e.deliveries
+        ),
+        _ => format!("{} {}", e.kind, e.stream),
+    };
+
+    Some(summary)
+}
+
+fn reason(reason: &str) -> String {
+    match reason {
+        "" => String::new(),
+        r => format!(": {}", r),
+    }
+}
+
+fn replicas(replicas: &[Replica]) -> String {
+    match replicas.len() {
+        0 => "no replicas".to_string(),
+        _ => replicas
+            .iter()
+            .map(|r| match r.current {
+                true => r.name.clone(),
+                false => format!("{} (lagging)", r.name),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advisory(kind: &str, fields: &str) -> String {
+        format!(r#"{{"type": "io.nats.jetstream.{}", {}}}"#, kind, fields)
+    }
+
+    #[test]
+    fn summarizes_advisories() {
+        let consumer = r#""stream": "ORDERS", "consumer": "worker""#;
+        let replicas = r#""replicas": [{"name": "n2", "current": true}, {"name": "n3"}]"#;
+        for (kind, fields, summary) in [
+            (
+                "advisory.v1.max_deliver",
+                format!(r#"{}, "stream_seq": 7, "deliveries": 5"#, consumer),
+                "consumer ORDERS > worker reached max deliveries (5) for stream seq 7",
+            ),
+            (
+                "advisory.v1.nak",
+                format!(r#"{}, "stream_seq": 7, "deliveries": 2"#, consumer),
+                "consumer ORDERS > worker NAKed stream seq 7 after 2 deliveries",
+            ),
+            (
+                "advisory.v1.terminated",
+                format!(
+                    r#"{}, "stream_seq": 7, "deliveries": 1, "reason": "bad data""#,
+                    consumer
+                ),
+                "consumer ORDERS > worker terminated stream seq 7 after 1 deliveries: bad data",
+            ),
+            (
+                "advisory.v1.terminated",
+                format!(r#"{}, "stream_seq": 7, "deliveries": 1"#, consumer),
+                "consumer ORDERS > worker terminated stream seq 7 after 1 deliveries",
+            ),
+            (
+                "advisory.v1.stream_leader_elected",
+                format!(r#""stream": "ORDERS", "leader": "n1", {}"#, replicas),
+                "stream ORDERS elected leader n1 (n2, n3 (lagging))",
+            ),
+            (
+                "advisory.v1.consumer_leader_elected",
+                format!(r#"{}, "leader": "n1", "replicas": []"#, consumer),
+                "consumer ORDERS > worker elected leader n1 (no replicas)",
+            ),
+            (
+                "advisory.v1.stream_quorum_lost",
+                format!(r#""stream": "ORDERS", {}"#, replicas),
+                "stream ORDERS lost quorum (n2, n3 (lagging))",
+            ),
+            (
+                "advisory.v1.consumer_quorum_lost",
+                format!(r#"{}, {}"#, consumer, replicas),
+                "consumer ORDERS > worker lost quorum (n2, n3 (lagging))",
+            ),
+            (
+                "advisory.v1.stream_action",
+                r#""stream": "ORDERS", "action": "create""#.to_string(),
+                "stream ORDERS create",
+            ),
+            (
+                "advisory.v1.consumer_action",
+                format!(r#"{}, "action": "delete""#, consumer),
+                "consumer ORDERS > worker delete",
+            ),
+            (
+                "advisory.v1.api_audit",
+                r#""subject": "$JS.API.STREAM.INFO.ORDERS", "client": {"acc": "APP", "name": "cli"}"#
+                    .to_string(),
+                "api $JS.API.STREAM.INFO.ORDERS called by cli (APP)",
+            ),
+            (
+                "metric.v1.consumer_ack",
+                format!(
+                    r#"{}, "stream_seq": 7, "deliveries": 1, "ack_time": 1500000"#,
+                    consumer
+                ),
+                "consumer ORDERS > worker acked stream seq 7 in 1.500 ms after 1 deliveries",
+            ),
+        ] {
+            assert_eq!(
+                summarize(
+                    "$JS.EVENT.ADVISORY.TEST",
+                    &advisory(kind, &fields)
+                )
+                .as_deref(),
+                Some(summary),
+                "{}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn summarizes_unknown_types() {
+        let payload =
+            r#"{"type": "io.nats.jetstream.advisory.v1.snapshot_create", "stream": "ORDERS"}"#;
+        assert_eq!(
+            summarize("$JS.EVENT.ADVISORY.STREAM.SNAPSHOT_CREATE.ORDERS", payload).as_deref(),
+            Some("io.nats.jetstream.advisory.v1.snapshot_create ORDERS")
+        );
+        assert_eq!(
+            summarize("$JS.EVENT.METRIC.X", "not json").as_deref(),
+            Some("unreadable advisory on $JS.EVENT.METRIC.X")
+        );
+    }
+
+    #[test]
+    fn ignores_other_subjects() {
+        let payload = advisory("advisory.v1.nak", r#""stream": "ORDERS""#);
+        assert_eq!(summarize("orders.new", &payload), None);
+        assert_eq!(summarize("$JS.EVENT", &payload), None);
+        assert!(!is_advisory("$JS.API.STREAM.INFO"));
+        assert!(is_advisory("$JS.EVENT.METRIC.CONSUMER.ACK.ORDERS.worker"));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    nats::{parse_headers, ClientCopy, NatsClient, SharedClient},
+    payload, template,
+};
+use anyhow::Result;
+use rand::{distributions::Alphanumeric, Rng};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// LoadConfig describes a repeated publish of the publish inputs
+#[derive(Clone)]
+pub struct LoadConfig {
+    pub subject: String,
+    pub headers: String,
+    pub payload: String,
+    pub variables: HashMap<String, String>,
+    // number of messages, None publishes until stopped
+    pub count: Option<u64>,
+    // messages per second over all threads, 0 is unlimited
+    pub rate: f64,
+    pub threads: usize,
+    // random payload size range replacing the payload
+    pub size: Option<(usize, usize)>,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            subject: String::new(),
+            headers: String::new(),
+            payload: String::new(),
+            variables: HashMap::new(),
+            count: None,
+            rate: 10.0,
+            threads: 1,
+            size: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    errors: AtomicU64,
+    seq: AtomicU64,
+    running: AtomicUsize,
+    // set by the last thread to finish
+    finished: Mutex<Option<Instant>>,
+    last_error: Mutex<Option<String>>,
+}
+
+// LoadGenerator publishes from its own threads until the count is reached or
+// it is stopped, on a copy of the session client per thread
+pub struct LoadGenerator {
+    stop: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+    started: Instant,
+    pub config: LoadConfig,
+}
+
+impl LoadGenerator {
+    pub fn start(config: LoadConfig, nc: SharedClient) -> Self {
+        let started = Instant::now();
+        let stop = Arc::new(AtomicBool::new(false));
+        let counters = Arc::new(Counters::default());
+        let threads = config.threads.max(1);
+
+        for i in 0..threads {
+            let count = config.count.map(|count| {
+                count / threads as u64 + u64::from((i as u64) < count % threads as u64)
+            });
+            let interval = match config.rate > 0.0 {
+                true => Some(Duration::from_secs_f64(threads as f64 / config.rate)),
+                false => None,
+            };
+
+            let config = config.clone();
+            let mut nc = ClientCopy::new(nc.clone());
+            let stop = stop.clone();
+            let counters = counters.clone();
+            counters.running.fetch_add(1, Ordering::SeqCst);
+            thread::spawn(move || {
+                let mut next = Instant::now();
+                let mut sent = 0;
+                while !stop.load(Ordering::SeqCst) && count.is_none_or(|count| sent < count) {
+                    if let Some(interval) = interval {
+                        let now = Instant::now();
+                        if next > now {
+                            thread::sleep(next - now);
+                        }
+                        next += interval;
+                    }
+
+                    let seq = counters.seq.fetch_add(1, Ordering::SeqCst) + 1;
+                    match publish(&config, seq, nc.get()) {
+                        Ok(_) => counters.sent.fetch_add(1, Ordering::SeqCst),
+                        Err(err) => {
+                            *counters.last_error.lock().unwrap() = Some(format!("{:#}", err));
+                            counters.errors.fetch_add(1, Ordering::SeqCst)
+                        }
+                    };
+                    sent += 1;
+                }
+                if counters.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    *counters.finished.lock().unwrap() = Some(Instant::now());
+                }
+            });
+        }
+
+        Self {
+            stop,
+            counters,
+            started,
+            config,
+        }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.counters.running.load(Ordering::SeqCst) > 0
+    }
+
+    pub fn sent(&self) -> u64 {
+        self.counters.sent.load(Ordering::SeqCst)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.counters.errors.load(Ordering::SeqCst)
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.counters.last_error.lock().unwrap().clone()
+    }
+
+    // elapsed stops growing once all threads finished
+    pub fn elapsed(&self) -> Duration {
+        match *self.counters.finished.lock().unwrap() {
+            Some(finished) => finished - self.started,
+            None => self.started.elapsed(),
+        }
+    }
+}
+
+fn publish(config: &LoadConfig, seq: u64, nc: &NatsClient) -> Result<()> {
+    let data = match config.size {
+        Some((min, max)) => {
+            let mut rng = rand::thread_rng();
+            let size = rng.gen_range(min, max + 1);
+            rng.sample_iter(Alphanumeric)
+                .take(size)
+                .map(|c| c as u8)
+                .collect()
+        }
+        None => payload::decode(&template::render(&config.payload, seq, &config.variables)?)?,
+    };
+    let subject = template::render(&config.subject, seq, &config.variables)?;
+    let headers = template::render(&config.headers, seq, &config.variables)?;
+
+    nc.publish_with_headers(subject, None, &parse_headers(&headers), data)
+}
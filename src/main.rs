@@ -1,16 +1,44 @@
 mod application;
+mod bench;
+mod bridge;
+mod config;
+mod context;
+mod diff;
+mod editor;
 mod events;
+mod jetstream;
+mod load;
+mod message;
+mod monitor;
 mod nats;
+mod payload;
+mod responder;
+mod services;
+mod status;
+mod system;
+mod template;
+mod trace;
+mod websocket;
 
-use crate::application::Application;
-use anyhow::Result;
-use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg};
+use crate::{
+    application::Application,
+    bench::{BenchConfig, BenchMode},
+    config::Profile,
+    events::Session,
+    monitor::MonitorClient,
+    nats::{ConnectOptions, NatsClient},
+    responder::Responder,
+};
+use anyhow::{bail, Result};
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, App, Arg, ArgMatches, SubCommand,
+};
 use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use scopeguard::defer;
-use std::io;
+use std::{env, io, time::Duration};
 use tui::{backend::CrosstermBackend, Terminal};
 use tui_logger::{init_logger, set_default_level};
 
@@ -24,14 +52,17 @@ fn main() -> Result<()> {
         .version(crate_version!())
         .arg(
             Arg::with_name("nats-url")
-                .help("NATS Server to establish a connection.")
+                .help("NATS Servers to establish a connection, separated by commas, ws:// and wss:// are supported.")
                 .short("n")
                 .long("nats-url")
-                .default_value("nats://localhost:4222"),
+                .env("NATS_URL")
+                .hide_env_values(true)
+                .default_value("nats://localhost:4222")
+                .global(true),
         )
         .arg(
             Arg::with_name("subject")
-                .help("Subscription subject for NATS connection.")
+                .help("Subscription subjects for NATS connection, separated by commas.")
                 .short("s")
                 .long("subject")
                 .default_value(">"),
@@ -40,34 +71,197 @@ fn main() -> Result<()> {
             Arg::with_name("username")
                 .help("Authenticate with NATS using username.")
                 .short("u")
-                .long("username"),
+                .long("username")
+                .env("NATS_USER")
+                .takes_value(true)
+                .global(true),
         )
         .arg(
             Arg::with_name("password")
-                .help("Authenticate with NATS using password.")
+                .help("Authenticate with NATS using password, asked if no value is given.")
                 .short("p")
-                .long("password"),
+                .long("password")
+                .env("NATS_PASSWORD")
+                .hide_env_values(true)
+                .takes_value(true)
+                .min_values(0)
+                .global(true),
         )
         .arg(
             Arg::with_name("token")
                 .help("Authenticate with NATS using a token.")
                 .short("t")
-                .long("token"),
+                .long("token")
+                .env("NATS_TOKEN")
+                .hide_env_values(true)
+                .takes_value(true)
+                .global(true),
         )
         .arg(
             Arg::with_name("credentials")
                 .help("Authenticate with NATS using a .creds file.")
                 .short("c")
-                .long("credentials"),
+                .long("credentials")
+                .env("NATS_CREDS")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("max-reconnects")
+                .help("Reconnect attempts per server before giving up, 'infinite' to never give up.")
+                .long("max-reconnects")
+                .default_value("10")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("reconnect-wait")
+                .help("Wait between reconnect attempts in milliseconds.")
+                .long("reconnect-wait")
+                .default_value("2000")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("reconnect-jitter")
+                .help("Random milliseconds added to the reconnect wait.")
+                .long("reconnect-jitter")
+                .default_value("100")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("reconnect-buffer")
+                .help("Bytes of published messages buffered while reconnecting.")
+                .long("reconnect-buffer")
+                .default_value("8388608")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("connect-timeout")
+                .help("Timeout of connecting to the servers in milliseconds.")
+                .long("connect-timeout")
+                .default_value("5000")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("monitor-url")
+                .help("NATS Server monitoring endpoint to poll for the dashboard.")
+                .short("m")
+                .long("monitor-url")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("monitor-interval")
+                .help("Polling interval of the monitoring endpoint in seconds.")
+                .long("monitor-interval")
+                .default_value("5"),
+        )
+        .arg(
+            Arg::with_name("responders")
+                .help("TOML file with mock responder rules.")
+                .short("r")
+                .long("responders")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("templates")
+                .help("TOML file with publish message templates.")
+                .long("templates")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("config")
+                .help("TOML config file with connection profiles. [default: $XDG_CONFIG_HOME/nats-spy/config.toml]")
+                .long("config")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .help("Connection profiles of the config file to use, separated by commas.")
+                .short("P")
+                .long("profile")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("context")
+                .help("Use a nats cli context, the selected one if no name is given.")
+                .long("context")
+                .takes_value(true)
+                .min_values(0)
+                .conflicts_with("profile")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("system")
+                .help("Subscribe to system account events. Requires system account credentials.")
+                .long("system"),
+        )
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about("Measures latency and throughput against the NATS Server.")
+                .arg(
+                    Arg::with_name("mode")
+                        .help("Publish and subscribe, or request from a built-in echo responder.")
+                        .long("mode")
+                        .possible_values(&["pubsub", "request"])
+                        .default_value("pubsub"),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .help("Number of messages.")
+                        .long("count")
+                        .default_value("10000"),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .help("Payload size in bytes.")
+                        .long("size")
+                        .default_value("128"),
+                ),
         )
         .get_matches();
 
-    let nats_url = config.value_of("nats-url").unwrap();
-    let subject = config.value_of("subject").unwrap();
-    let username = config.value_of("username");
-    let password = config.value_of("password");
-    let token = config.value_of("token");
-    let credentials = config.value_of("credentials");
+    let file = config::load(config.value_of("config"))?;
+    let mut profiles = match (config.values_of("profile"), config.is_present("context")) {
+        (Some(names), _) => names
+            .map(|name| match file.profile.get(name) {
+                Some(profile) => Ok((name.to_string(), profile.clone())),
+                None => bail!("Profile '{}' not found.", name),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        (None, true) => vec![(
+            config.value_of("context").unwrap_or("context").to_string(),
+            context::load(config.value_of("context"))?,
+        )],
+        (None, false) => Vec::new(),
+    };
+
+    if let Some(bench) = config.subcommand_matches("bench") {
+        let bench_config = BenchConfig {
+            mode: match bench.value_of("mode") {
+                Some("request") => BenchMode::RequestReply,
+                _ => BenchMode::PubSub,
+            },
+            count: bench.value_of("count").unwrap().parse()?,
+            size: bench.value_of("size").unwrap().parse()?,
+        };
+
+        let profile = profiles
+            .into_iter()
+            .next()
+            .map(|(_, profile)| profile)
+            .unwrap_or_default();
+        let password = match needs_password(&config, &profile) {
+            true => Some(rpassword::prompt_password("Password: ")?),
+            false => password(&config, &profile),
+        };
+        let mut nats_client = nats_client(&config, &profile, password)?;
+        nats_client.connect()?;
+        println!("{}", bench::run(bench_config, &nats_client)?);
+        return Ok(());
+    }
 
     // initialize terminal
     setup_terminal()?;
@@ -78,28 +272,177 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
+    // pick a profile when no server is given
+    if profiles.is_empty() && !url_given(&config) && !file.profile.is_empty() {
+        let names = file.profile.keys().cloned().collect::<Vec<_>>();
+        if let Some(i) = application::pick_profile(&mut terminal, &names)? {
+            profiles.push((names[i].clone(), file.profile[&names[i]].clone()));
+        }
+    }
+    if profiles.is_empty() {
+        profiles.push(("default".to_string(), Profile::default()));
+    }
+
+    // every profile is a session, the first one provides the tools
+    let mut sessions = Vec::new();
+    for (name, profile) in &profiles {
+        // ask the password outside of the tui
+        let password = match needs_password(&config, profile) {
+            true => {
+                let prompt = match profiles.len() {
+                    1 => "Password: ".to_string(),
+                    _ => format!("Password for {}: ", name),
+                };
+                stop_terminal()?;
+                let password = rpassword::prompt_password(prompt);
+                setup_terminal()?;
+                terminal.clear()?;
+                Some(password?)
+            }
+            false => password(&config, profile),
+        };
+
+        let subjects = match (
+            config.occurrences_of("subject"),
+            profile.subscriptions.is_empty(),
+        ) {
+            (0, false) => profile.subscriptions.clone(),
+            _ => config
+                .value_of("subject")
+                .unwrap()
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+        };
+
+        sessions.push(Session {
+            name: name.clone(),
+            nats_client: nats_client(&config, profile, password)?,
+            subjects,
+            system: config.is_present("system") || profile.system,
+        });
+    }
+
+    let profile = &profiles[0].1;
+    let monitor_interval = match (
+        config.occurrences_of("monitor-interval"),
+        profile.monitor_interval,
+    ) {
+        (0, Some(interval)) => interval,
+        _ => config
+            .value_of("monitor-interval")
+            .unwrap()
+            .parse::<u64>()?,
+    };
+    let monitor = config
+        .value_of("monitor-url")
+        .map(str::to_string)
+        .or_else(|| profile.monitor_url.clone())
+        .map(|url| MonitorClient::new(url, Duration::from_secs(monitor_interval)));
+    let responder = match config
+        .value_of("responders")
+        .or(profile.responders.as_deref())
+    {
+        Some(path) => Responder::new(responder::load(path)?),
+        None => Responder::default(),
+    };
+    let templates = match config
+        .value_of("templates")
+        .or(profile.templates.as_deref())
+    {
+        Some(path) => template::load(path)?,
+        None => Vec::new(),
+    };
+
     // start terminal
-    let mut app = Application::new(
-        nats_url.to_string(),
-        subject.to_string(),
-        username.map(str::to_string),
-        password.map(str::to_string),
-        token.map(str::to_string),
-        credentials.map(str::to_string),
-    );
+    let mut app = Application::new(sessions, monitor, responder, templates, profile.ui.clone());
     app.draw(&mut terminal)?;
 
     Ok(())
 }
 
-fn setup_terminal() -> Result<()> {
+// url_given checks whether the server is set by flag or environment
+fn url_given(config: &ArgMatches) -> bool {
+    config.occurrences_of("nats-url") > 0 || env::var_os("NATS_URL").is_some()
+}
+
+// value of the flag or its environment variable, falling back to the profile
+fn value(config: &ArgMatches, name: &str, fallback: &Option<String>) -> Option<String> {
+    config
+        .value_of(name)
+        .map(str::to_string)
+        .or_else(|| fallback.clone())
+}
+
+fn password(config: &ArgMatches, profile: &Profile) -> Option<String> {
+    value(config, "password", &profile.password)
+}
+
+// needs_password checks whether a username or an empty --password flag is
+// given without a password
+fn needs_password(config: &ArgMatches, profile: &Profile) -> bool {
+    password(config, profile).is_none()
+        && (value(config, "username", &profile.username).is_some() || config.is_present("password"))
+}
+
+// nats_client uses the connection flags, falling back to the profile
+fn nats_client(
+    config: &ArgMatches,
+    profile: &Profile,
+    password: Option<String>,
+) -> Result<NatsClient> {
+    let url = match (url_given(config), &profile.url) {
+        (false, Some(url)) => url.clone(),
+        _ => config.value_of("nats-url").unwrap().to_string(),
+    };
+
+    Ok(NatsClient::new(
+        url,
+        value(config, "username", &profile.username),
+        password,
+        value(config, "token", &profile.token),
+        value(config, "credentials", &profile.credentials),
+        profile.nkey.clone(),
+    )
+    .with_tls(profile.tls.clone())
+    .with_options(connect_options(config)?))
+}
+
+fn connect_options(config: &ArgMatches) -> Result<ConnectOptions> {
+    let millis = |name: &str| -> Result<Duration> {
+        match config.value_of(name).unwrap().parse() {
+            Ok(ms) => Ok(Duration::from_millis(ms)),
+            Err(_) => bail!("Invalid --{}, expected milliseconds.", name),
+        }
+    };
+
+    Ok(ConnectOptions {
+        max_reconnects: match config.value_of("max-reconnects").unwrap() {
+            "infinite" => None,
+            max => match max.parse() {
+                Ok(max) => Some(max),
+                Err(_) => bail!("Invalid --max-reconnects, expected a number or 'infinite'."),
+            },
+        },
+        reconnect_wait: millis("reconnect-wait")?,
+        reconnect_jitter: millis("reconnect-jitter")?,
+        reconnect_buffer_size: match config.value_of("reconnect-buffer").unwrap().parse() {
+            Ok(size) => size,
+            Err(_) => bail!("Invalid --reconnect-buffer, expected bytes."),
+        },
+        connect_timeout: millis("connect-timeout")?,
+    })
+}
+
+pub fn setup_terminal() -> Result<()> {
     enable_raw_mode()?;
     io::stdout().execute(EnterAlternateScreen)?;
 
     Ok(())
 }
 
-fn stop_terminal() -> Result<()> {
+pub fn stop_terminal() -> Result<()> {
     disable_raw_mode()?;
     io::stdout().execute(LeaveAlternateScreen)?;
 
@@ -0,0 +1,65 @@
+use crate::jetstream;
+use chrono::Local;
+use std::time::{Duration, Instant};
+
+#[derive(Clone)]
+pub struct Message {
+    pub subject: String,
+    pub reply: Option<String>,
+    pub payload: String,
+    // raw bytes of the payload to republish the message unchanged
+    pub data: Vec<u8>,
+    pub headers: Vec<(String, String)>,
+    pub received: Instant,
+    pub time: String,
+    // human readable line of jetstream advisories and metrics
+    pub summary: Option<String>,
+    // index of the paired request or reply
+    pub link: Option<usize>,
+    // round trip time of a request that has been answered
+    pub latency: Option<Duration>,
+    // index of the session the message was received on
+    pub session: usize,
+}
+
+impl Message {
+    pub fn new(
+        subject: String,
+        reply: Option<String>,
+        headers: Vec<(String, String)>,
+        data: &[u8],
+    ) -> Self {
+        let payload = String::from_utf8_lossy(data).to_string();
+
+        Self {
+            summary: jetstream::summarize(&subject, &payload),
+            subject,
+            reply,
+            payload,
+            data: data.to_vec(),
+            headers,
+            received: Instant::now(),
+            time: Local::now().format("%H:%M:%S%.3f").to_string(),
+            link: None,
+            latency: None,
+            session: 0,
+        }
+    }
+}
+
+impl From<nats::Message> for Message {
+    fn from(msg: nats::Message) -> Self {
+        let mut headers = msg
+            .headers
+            .map(|h| {
+                h.inner
+                    .into_iter()
+                    .flat_map(|(k, values)| values.into_iter().map(move |v| (k.clone(), v)))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        headers.sort();
+
+        Self::new(msg.subject, msg.reply, headers, &msg.data)
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{bail, Result};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::time::Duration;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Varz {
+    pub server_id: String,
+    pub server_name: String,
+    pub version: String,
+    pub host: String,
+    pub port: u16,
+    pub uptime: String,
+    pub cores: u32,
+    pub cpu: f64,
+    pub mem: u64,
+    pub max_payload: u64,
+    pub connections: u64,
+    pub total_connections: u64,
+    pub routes: u64,
+    pub remotes: u64,
+    pub leafnodes: u64,
+    pub subscriptions: u64,
+    pub in_msgs: u64,
+    pub out_msgs: u64,
+    pub in_bytes: u64,
+    pub out_bytes: u64,
+    pub slow_consumers: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ConnInfo {
+    pub cid: u64,
+    pub name: String,
+    pub ip: String,
+    pub port: u16,
+    pub lang: String,
+    pub version: String,
+    pub pending_bytes: u64,
+    pub in_msgs: u64,
+    pub out_msgs: u64,
+    pub in_bytes: u64,
+    pub out_bytes: u64,
+    pub rtt: String,
+    pub uptime: String,
+    pub subscriptions: u64,
+    pub subscriptions_list: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Connz {
+    pub num_connections: u64,
+    pub total: u64,
+    pub connections: Vec<ConnInfo>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Routez {
+    pub num_routes: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Subsz {
+    pub num_subscriptions: u64,
+    pub num_cache: u64,
+    pub num_matches: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct JszConfig {
+    pub max_memory: u64,
+    pub max_storage: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Jsz {
+    pub disabled: bool,
+    pub config: JszConfig,
+    pub memory: u64,
+    pub storage: u64,
+    pub streams: u64,
+    pub consumers: u64,
+    pub messages: u64,
+    pub bytes: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Healthz {
+    pub status: String,
+    pub error: Option<String>,
+}
+
+// server state collected from the monitoring endpoints in a single poll
+#[derive(Clone, Default)]
+pub struct ServerStats {
+    pub varz: Varz,
+    pub connz: Connz,
+    pub routez: Routez,
+    pub subsz: Subsz,
+    pub jsz: Option<Jsz>,
+    pub healthz: Healthz,
+}
+
+// response of the $SYS.REQ.SERVER.PING.<endpoint> system requests
+#[derive(Deserialize)]
+pub struct ServerApiResponse<T> {
+    pub data: Option<T>,
+}
+
+#[derive(Clone)]
+pub struct MonitorClient {
+    url: String,
+    interval: Duration,
+    agent: ureq::Agent,
+}
+
+impl MonitorClient {
+    pub fn new(url: String, interval: Duration) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            interval,
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(2))
+                .build(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    // poll fetches all monitoring endpoints of the server
+    pub fn poll(&self) -> Result<ServerStats> {
+        Ok(ServerStats {
+            varz: self.get("varz")?,
+            connz: self.get("connz?subs=1")?,
+            routez: self.get("routez")?,
+            subsz: self.get("subsz")?,
+            // jsz fails on servers without jetstream
+            jsz: self.get("jsz").ok(),
+            healthz: self.get("healthz")?,
+        })
+    }
+
+    fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let url = format!("{}/{}", self.url, endpoint);
+        let resp = match self.agent.get(url.as_str()).call() {
+            Ok(resp) => resp,
+            // healthz answers with an error status but still has a json body
+            Err(ureq::Error::Status(_, resp)) => resp,
+            Err(err) => bail!("Cannot poll '{}'. {}", url, err),
+        };
+
+        match resp.into_json() {
+            Ok(body) => Ok(body),
+            Err(err) => bail!("Cannot parse '{}'. {}", url, err),
+        }
+    }
+}
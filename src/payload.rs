@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use std::fs;
+
+// decode reads the payload of the publish and request inputs. "@path" sends the
+// file contents, "hex:" and "base64:" decode binary data, anything else is sent
+// as written.
+pub fn decode(input: &str) -> Result<Vec<u8>> {
+    if let Some(path) = input.strip_prefix('@') {
+        let path = path.trim();
+        return fs::read(path).with_context(|| format!("Cannot read '{}'", path));
+    }
+
+    if let Some(data) = input.strip_prefix("hex:") {
+        return hex::decode(without_whitespace(data)).context("Invalid hex payload");
+    }
+
+    if let Some(data) = input.strip_prefix("base64:") {
+        return base64::decode(without_whitespace(data)).context("Invalid base64 payload");
+    }
+
+    Ok(input.as_bytes().to_vec())
+}
+
+// encode turns a received payload into input that decode reads back
+// unchanged, payloads that are not utf-8 or look like a prefix are base64
+pub fn encode(data: &[u8]) -> String {
+    match std::str::from_utf8(data) {
+        Ok(text) if !has_prefix(text) => text.to_string(),
+        _ => format!("base64:{}", base64::encode(data)),
+    }
+}
+
+fn has_prefix(input: &str) -> bool {
+    input.starts_with('@') || input.starts_with("hex:") || input.starts_with("base64:")
+}
+
+fn without_whitespace(data: &str) -> String {
+    data.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_keeps_text() {
+        assert_eq!(decode("").unwrap(), b"");
+        assert_eq!(decode(" {\"a\": 1}\n").unwrap(), b" {\"a\": 1}\n");
+        assert_eq!(decode("HEX:00").unwrap(), b"HEX:00");
+        assert_eq!(decode(" @file").unwrap(), b" @file");
+    }
+
+    #[test]
+    fn decode_hex() {
+        assert_eq!(decode("hex:").unwrap(), b"");
+        assert_eq!(decode("hex:00ff").unwrap(), [0x00, 0xff]);
+        assert_eq!(decode("hex: 0A 0b\n\tFF ").unwrap(), [0x0a, 0x0b, 0xff]);
+        assert!(decode("hex:abc").is_err());
+        assert!(decode("hex:zz").is_err());
+    }
+
+    #[test]
+    fn decode_base64() {
+        assert_eq!(decode("base64:").unwrap(), b"");
+        assert_eq!(decode("base64:aGVs\nbG8=").unwrap(), b"hello");
+        assert_eq!(decode("base64:aGVsbG8").unwrap(), b"hello");
+        assert!(decode("base64:*").is_err());
+    }
+
+    #[test]
+    fn decode_file() {
+        let path = std::env::temp_dir().join(format!("nats-spy-payload-{}", std::process::id()));
+        fs::write(&path, [0xff, 0x00]).unwrap();
+        let decoded = decode(&format!("@ {}\n", path.display()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(decoded.unwrap(), [0xff, 0x00]);
+
+        let err = decode("@/nonexistent/payload").unwrap_err();
+        assert_eq!(err.to_string(), "Cannot read '/nonexistent/payload'");
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        for data in [
+            &b"plain text"[..],
+            b"@/etc/passwd",
+            b"hex:00ff",
+            b"base64:AAEC",
+            &[0xff, 0x00, 0xfe],
+            b"",
+        ] {
+            assert_eq!(decode(&encode(data)).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn encode_keeps_text() {
+        assert_eq!(encode("{\"a\": 1}".as_bytes()), "{\"a\": 1}");
+        assert_eq!(encode(b"@/etc/passwd"), "base64:QC9ldGMvcGFzc3dk");
+        assert_eq!(encode(&[0xff]), "base64:/w==");
+    }
+}
@@ -0,0 +1,394 @@
+use crate::nats::{subject_matches, NatsClient};
+use anyhow::{Context, Result};
+use log::{error, info};
+use nats::Subscription;
+use regex::bytes::Regex;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+// status of an error rule without error_code
+const DEFAULT_ERROR_CODE: u16 = 500;
+
+#[derive(Deserialize)]
+struct RuleConfig {
+    subject: String,
+    payload: Option<String>,
+    #[serde(default)]
+    reply: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    delay_ms: u64,
+    error: Option<String>,
+    error_code: Option<u16>,
+}
+
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    responder: Vec<RuleConfig>,
+}
+
+#[derive(Clone)]
+pub struct Rule {
+    pub subject: String,
+    pub payload: Option<Regex>,
+    pub reply: String,
+    pub headers: Vec<(String, String)>,
+    pub delay: Duration,
+    pub error: Option<(u16, String)>,
+    pub hits: u64,
+}
+
+impl Rule {
+    pub fn new(
+        subject: String,
+        payload: Option<&str>,
+        reply: String,
+        headers: Vec<(String, String)>,
+        delay: Duration,
+        error: Option<(u16, String)>,
+    ) -> Result<Self> {
+        let payload = match payload {
+            Some(p) => {
+                Some(Regex::new(p).with_context(|| format!("Invalid payload regex '{}'", p))?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            subject,
+            payload,
+            reply,
+            headers,
+            delay,
+            error,
+            hits: 0,
+        })
+    }
+
+    // matches checks the subject of a request against the rule subject
+    // including wildcards, and the payload against the regex
+    fn matches(&self, subject: &str, data: &[u8]) -> bool {
+        subject_matches(&self.subject, subject)
+            && self.payload.as_ref().is_none_or(|p| p.is_match(data))
+    }
+
+    // headers of the reply including the nats micro error headers
+    fn reply_headers(&self) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+        if let Some((code, description)) = &self.error {
+            headers.push(("Nats-Service-Error".to_string(), description.clone()));
+            headers.push(("Nats-Service-Error-Code".to_string(), code.to_string()));
+        }
+
+        headers
+    }
+}
+
+// load reads [[responder]] rules from a toml file
+pub fn load(path: &str) -> Result<Vec<Rule>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Cannot read '{}'", path))?;
+    let file: RulesFile =
+        toml::from_str(&content).with_context(|| format!("Cannot parse '{}'", path))?;
+
+    file.responder
+        .into_iter()
+        .map(|r| {
+            let error = match (r.error_code, r.error) {
+                (None, None) => None,
+                (code, description) => Some((
+                    code.unwrap_or(DEFAULT_ERROR_CODE),
+                    description.unwrap_or_default(),
+                )),
+            };
+            Rule::new(
+                r.subject,
+                r.payload.as_deref(),
+                r.reply,
+                r.headers.into_iter().collect(),
+                Duration::from_millis(r.delay_ms),
+                error,
+            )
+        })
+        .collect()
+}
+
+// Responder answers requests on the subjects of its rules with canned replies
+#[derive(Clone, Default)]
+pub struct Responder {
+    rules: Arc<Mutex<Vec<Rule>>>,
+    // one subscription per rule subject
+    subscribed: Arc<Mutex<HashMap<String, Subscription>>>,
+}
+
+impl Responder {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules: Arc::new(Mutex::new(rules)),
+            subscribed: Arc::default(),
+        }
+    }
+
+    pub fn rules(&self) -> Vec<Rule> {
+        self.rules.lock().unwrap().clone()
+    }
+
+    // start subscribes the subjects of all rules once connected
+    pub fn start(&self, nc: &NatsClient) {
+        // subscriptions of an earlier connection are gone
+        self.subscribed.lock().unwrap().clear();
+
+        let subjects = self
+            .rules
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.subject.clone())
+            .collect::<Vec<_>>();
+
+        for subject in subjects {
+            self.subscribe(subject, nc);
+        }
+    }
+
+    // add stores the rule and subscribes its subject if connected
+    pub fn add(&self, rule: Rule, nc: &NatsClient) {
+        let subject = rule.subject.clone();
+        self.rules.lock().unwrap().push(rule);
+        info!("Responder added for subject '{}'.", subject);
+
+        if nc.is_connected() {
+            self.subscribe(subject, nc);
+        }
+    }
+
+    // remove deletes the rule and unsubscribes its subject unless another rule
+    // uses it
+    pub fn remove(&self, index: usize) {
+        let mut rules = self.rules.lock().unwrap();
+        if index >= rules.len() {
+            return;
+        }
+
+        let rule = rules.remove(index);
+        info!("Responder removed for subject '{}'.", rule.subject);
+        if rules.iter().any(|r| r.subject == rule.subject) {
+            return;
+        }
+        if let Some(sub) = self.subscribed.lock().unwrap().remove(&rule.subject) {
+            if let Err(err) = sub.unsubscribe() {
+                error!("Cannot unsubscribe '{}'. {}", rule.subject, err);
+            }
+        }
+    }
+
+    fn subscribe(&self, subject: String, nc: &NatsClient) {
+        let mut subscribed = self.subscribed.lock().unwrap();
+        if subscribed.contains_key(&subject) {
+            return;
+        }
+
+        match nc.subscribe(subject.clone()) {
+            Ok(sub) => {
+                subscribed.insert(subject.clone(), sub.clone());
+                self.listen(sub, subject, nc.clone());
+            }
+            Err(err) => error!("{}", err),
+        }
+    }
+
+    // listen answers requests with the first matching rule. A request on
+    // overlapping subjects arrives on every subscription, only the one of the
+    // matching rule answers it.
+    fn listen(&self, sub: Subscription, subject: String, nc: NatsClient) {
+        let rules = self.rules.clone();
+        thread::spawn(move || {
+            for msg in sub.messages() {
+                let reply = match &msg.reply {
+                    Some(reply) => reply.clone(),
+                    None => continue,
+                };
+
+                let rule = {
+                    let mut rules = rules.lock().unwrap();
+                    match rules
+                        .iter_mut()
+                        .find(|r| r.matches(&msg.subject, &msg.data))
+                    {
+                        Some(rule) if rule.subject == subject => {
+                            rule.hits += 1;
+                            rule.clone()
+                        }
+                        _ => continue,
+                    }
+                };
+
+                let nc = nc.clone();
+                thread::spawn(move || {
+                    thread::sleep(rule.delay);
+                    match nc.publish_with_headers(
+                        reply,
+                        None,
+                        &rule.reply_headers(),
+                        rule.reply.clone(),
+                    ) {
+                        Ok(_) => info!(
+                            "Responder '{}' answered request on '{}'.",
+                            rule.subject, msg.subject
+                        ),
+                        Err(err) => error!("{}", err),
+                    }
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_rules(content: &str) -> Result<Vec<Rule>> {
+        let path = std::env::temp_dir().join(format!(
+            "nats-spy-responders-{}-{:016x}.toml",
+            std::process::id(),
+            rand::random::<u64>()
+        ));
+        fs::write(&path, content).unwrap();
+        let rules = load(&path.to_string_lossy());
+        fs::remove_file(&path).unwrap();
+        rules
+    }
+
+    #[test]
+    fn loads_rules() {
+        let rules = load_rules(
+            r#"
+            [[responder]]
+            subject = "orders.get"
+            payload = '"id":\s*42'
+            reply = '{"id": 42}'
+            headers = { "Content-Type" = "application/json" }
+            delay_ms = 100
+
+            [[responder]]
+            subject = "orders.cancel"
+            error = "service unavailable"
+            error_code = 503
+
+            [[responder]]
+            subject = "orders.fail"
+            error = "boom"
+
+            [[responder]]
+            subject = "orders.teapot"
+            error_code = 418
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 4);
+        assert_eq!(rules[0].reply, r#"{"id": 42}"#);
+        assert_eq!(
+            rules[0].headers,
+            [("Content-Type".to_string(), "application/json".to_string())]
+        );
+        assert_eq!(rules[0].delay, Duration::from_millis(100));
+        assert_eq!(rules[0].error, None);
+        assert_eq!(
+            rules[1].error,
+            Some((503, "service unavailable".to_string()))
+        );
+        assert_eq!(rules[2].error, Some((500, "boom".to_string())));
+        assert_eq!(rules[3].error, Some((418, String::new())));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let err = load_rules("[[responder]]\nsubject = \"a\"\npayload = \"(\"\n").err();
+        assert_eq!(
+            err.map(|err| err.to_string()).as_deref(),
+            Some("Invalid payload regex '('")
+        );
+        assert!(load_rules("[[responder]]\nreply = \"no subject\"\n").is_err());
+        assert!(load_rules("").unwrap().is_empty());
+        assert!(load("/nonexistent/responders.toml").is_err());
+    }
+
+    #[test]
+    fn matches_payload_regex() {
+        let rule = |payload| {
+            Rule::new(
+                "orders.get".to_string(),
+                payload,
+                String::new(),
+                Vec::new(),
+                Duration::default(),
+                None,
+            )
+            .unwrap()
+        };
+
+        assert!(rule(None).matches("orders.get", b""));
+        assert!(!rule(None).matches("orders.list", b""));
+        let by_id = rule(Some(r#""id":\s*42\b"#));
+        assert!(by_id.matches("orders.get", br#"{"id": 42}"#));
+        assert!(!by_id.matches("orders.get", br#"{"id": 421}"#));
+        assert!(by_id.matches("orders.get", b"\xff\"id\":42"));
+    }
+
+    #[test]
+    fn matches_wildcard_subjects() {
+        let rule = |subject: &str| {
+            Rule::new(
+                subject.to_string(),
+                None,
+                String::new(),
+                Vec::new(),
+                Duration::default(),
+                None,
+            )
+            .unwrap()
+        };
+
+        assert!(rule("orders.*").matches("orders.get", b""));
+        assert!(!rule("orders.*").matches("orders.get.eu", b""));
+        assert!(rule("orders.>").matches("orders.get.eu", b""));
+        assert!(!rule("orders.>").matches("payments.get", b""));
+    }
+
+    #[test]
+    fn adds_micro_error_headers() {
+        let rule = |error| {
+            Rule::new(
+                "svc".to_string(),
+                None,
+                String::new(),
+                vec![("K".to_string(), "v".to_string())],
+                Duration::default(),
+                error,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            rule(None).reply_headers(),
+            [("K".to_string(), "v".to_string())]
+        );
+        assert_eq!(
+            rule(Some((503, "down".to_string()))).reply_headers(),
+            [
+                ("K".to_string(), "v".to_string()),
+                ("Nats-Service-Error".to_string(), "down".to_string()),
+                ("Nats-Service-Error-Code".to_string(), "503".to_string()),
+            ]
+        );
+    }
+}
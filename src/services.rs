@@ -0,0 +1,245 @@
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::BTreeMap;
+
+pub const PING_SUBJECT: &str = "$SRV.PING";
+pub const INFO_SUBJECT: &str = "$SRV.INFO";
+pub const STATS_SUBJECT: &str = "$SRV.STATS";
+pub const SCHEMA_SUBJECT: &str = "$SRV.SCHEMA";
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Schema {
+    pub request: String,
+    pub response: String,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EndpointInfo {
+    pub name: String,
+    pub subject: String,
+    pub queue_group: String,
+    pub metadata: Option<BTreeMap<String, String>>,
+    pub schema: Option<Schema>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EndpointStats {
+    pub name: String,
+    pub subject: String,
+    pub num_requests: u64,
+    pub num_errors: u64,
+    pub last_error: String,
+    pub average_processing_time: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+struct PingResponse {
+    name: String,
+    id: String,
+    version: String,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+struct InfoResponse {
+    id: String,
+    description: String,
+    endpoints: Vec<EndpointInfo>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+struct StatsResponse {
+    id: String,
+    endpoints: Vec<EndpointStats>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+struct EndpointSchema {
+    name: String,
+    schema: Option<Schema>,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+struct SchemaResponse {
+    endpoints: Vec<EndpointSchema>,
+}
+
+#[derive(Clone, Default)]
+pub struct Endpoint {
+    pub info: EndpointInfo,
+    pub stats: EndpointStats,
+}
+
+#[derive(Clone, Default)]
+pub struct Instance {
+    pub service: String,
+    pub id: String,
+    pub version: String,
+    pub description: String,
+    pub endpoints: Vec<Endpoint>,
+}
+
+// discover merges the responses of $SRV.PING, $SRV.INFO and $SRV.STATS into
+// service instances sorted by service name, skipping unreadable responses
+pub fn discover(ping: &[Vec<u8>], info: &[Vec<u8>], stats: &[Vec<u8>]) -> Vec<Instance> {
+    let mut instances = parse::<PingResponse>(ping)
+        .into_iter()
+        .map(|p| Instance {
+            service: p.name,
+            id: p.id,
+            version: p.version,
+            ..Instance::default()
+        })
+        .collect::<Vec<_>>();
+
+    for i in parse::<InfoResponse>(info) {
+        if let Some(instance) = instances.iter_mut().find(|instance| instance.id == i.id) {
+            instance.description = i.description;
+            instance.endpoints = i
+                .endpoints
+                .into_iter()
+                .map(|info| Endpoint {
+                    info,
+                    stats: EndpointStats::default(),
+                })
+                .collect();
+        }
+    }
+
+    for s in parse::<StatsResponse>(stats) {
+        if let Some(instance) = instances.iter_mut().find(|instance| instance.id == s.id) {
+            for stats in s.endpoints {
+                match instance
+                    .endpoints
+                    .iter_mut()
+                    .find(|e| e.info.name == stats.name)
+                {
+                    Some(endpoint) => endpoint.stats = stats,
+                    None => instance.endpoints.push(Endpoint {
+                        info: EndpointInfo {
+                            name: stats.name.clone(),
+                            subject: stats.subject.clone(),
+                            ..EndpointInfo::default()
+                        },
+                        stats,
+                    }),
+                }
+            }
+        }
+    }
+
+    instances.sort_by(|a, b| (&a.service, &a.id).cmp(&(&b.service, &b.id)));
+
+    instances
+}
+
+// request_schema reads the request schema of the endpoint from the
+// $SRV.SCHEMA.<name>.<id> response of its instance
+pub fn request_schema(response: &[u8], endpoint: &str) -> Option<String> {
+    serde_json::from_slice::<SchemaResponse>(response)
+        .ok()?
+        .endpoints
+        .into_iter()
+        .find(|e| e.name == endpoint)?
+        .schema
+        .map(|s| s.request)
+        .filter(|s| !s.is_empty())
+}
+
+fn parse<T: DeserializeOwned>(responses: &[Vec<u8>]) -> Vec<T> {
+    responses
+        .iter()
+        .filter_map(|resp| serde_json::from_slice(resp).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responses(responses: &[&str]) -> Vec<Vec<u8>> {
+        responses.iter().map(|r| r.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn merges_responses_by_instance() {
+        let ping = responses(&[
+            r#"{"name": "orders", "id": "b", "version": "1.1.0"}"#,
+            r#"{"name": "billing", "id": "c", "version": "0.1.0"}"#,
+            r#"{"name": "orders", "id": "a", "version": "1.0.0"}"#,
+        ]);
+        let info = responses(&[
+            r#"{"id": "a", "description": "Orders", "endpoints": [
+                {"name": "create", "subject": "orders.create", "queue_group": "q"},
+                {"name": "list", "subject": "orders.list"}
+            ]}"#,
+            r#"{"id": "unknown", "endpoints": [{"name": "x", "subject": "x"}]}"#,
+        ]);
+        let stats = responses(&[r#"{"id": "a", "endpoints": [
+            {"name": "list", "subject": "orders.list", "num_requests": 3, "num_errors": 1},
+            {"name": "delete", "subject": "orders.delete", "num_requests": 2}
+        ]}"#]);
+
+        let instances = discover(&ping, &info, &stats);
+        let ids = instances.iter().map(|i| i.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["c", "a", "b"]);
+
+        let orders = &instances[1];
+        assert_eq!(orders.service, "orders");
+        assert_eq!(orders.version, "1.0.0");
+        assert_eq!(orders.description, "Orders");
+        let endpoints = orders
+            .endpoints
+            .iter()
+            .map(|e| (e.info.subject.as_str(), e.stats.num_requests))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            endpoints,
+            [
+                ("orders.create", 0),
+                ("orders.list", 3),
+                ("orders.delete", 2)
+            ]
+        );
+        assert_eq!(orders.endpoints[0].info.queue_group, "q");
+        assert_eq!(orders.endpoints[1].stats.num_errors, 1);
+
+        assert!(instances[2].endpoints.is_empty());
+    }
+
+    #[test]
+    fn skips_unreadable_responses() {
+        let ping = responses(&["not json", r#"{"name": "orders", "id": "a"}"#, ""]);
+        let info = responses(&[r#"{"id": "a", "endpoints": "oops"}"#]);
+
+        let instances = discover(&ping, &info, &[]);
+        assert_eq!(instances.len(), 1);
+        assert!(instances[0].endpoints.is_empty());
+        assert!(discover(&[], &info, &[]).is_empty());
+    }
+
+    #[test]
+    fn reads_request_schema() {
+        let response = br#"{"name": "orders", "id": "a", "endpoints": [
+            {"name": "create", "subject": "orders.create",
+             "schema": {"request": "{\"type\": \"object\"}", "response": ""}},
+            {"name": "list", "subject": "orders.list", "schema": {"request": ""}},
+            {"name": "get", "subject": "orders.get"}
+        ]}"#;
+
+        assert_eq!(
+            request_schema(response, "create").as_deref(),
+            Some(r#"{"type": "object"}"#)
+        );
+        assert_eq!(request_schema(response, "list"), None);
+        assert_eq!(request_schema(response, "get"), None);
+        assert_eq!(request_schema(response, "missing"), None);
+        assert_eq!(request_schema(b"{}", "create"), None);
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::{
+    io::{BufRead, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Reconnecting,
+    Closed,
+}
+
+impl ConnectionState {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ConnectionState::Connecting => "connecting",
+            ConnectionState::Connected => "connected",
+            ConnectionState::Reconnecting => "reconnecting",
+            ConnectionState::Closed => "closed",
+        }
+    }
+}
+
+// ServerInfo is the INFO line a server sends to every new connection
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerInfo {
+    pub server_id: String,
+    pub server_name: String,
+    pub version: String,
+    pub max_payload: u64,
+    pub connect_urls: Vec<String>,
+}
+
+// ConnectionStatus is updated by the connection callbacks and read by the
+// status bar
+pub struct ConnectionStatus {
+    pub state: ConnectionState,
+    pub server: Option<ServerInfo>,
+    // the server info was read from a server of the list, which is not
+    // necessarily the one the client is connected to
+    pub seed: bool,
+    pub rtt: Option<Duration>,
+    pub reconnects: Vec<DateTime<Local>>,
+    // failed attempts of connecting and the time of the next one
+    pub connect_attempts: u32,
+    pub retry_at: Option<Instant>,
+}
+
+impl Default for ConnectionStatus {
+    fn default() -> Self {
+        Self {
+            state: ConnectionState::Connecting,
+            server: None,
+            seed: false,
+            rtt: None,
+            reconnects: Vec::new(),
+            connect_attempts: 0,
+            retry_at: None,
+        }
+    }
+}
+
+pub type SharedStatus = Arc<Mutex<ConnectionStatus>>;
+
+// probe reads the INFO of the first reachable server of the comma-separated
+// urls, servers send it in plain text even when tls is required
+pub fn probe(urls: &str) -> Result<ServerInfo> {
+    let mut last_error = None;
+    for url in urls.split(',').map(str::trim).filter(|u| !u.is_empty()) {
+        match probe_server(url) {
+            Ok(info) => return Ok(info),
+            Err(err) => last_error = Some(err),
+        }
+    }
+
+    match last_error {
+        Some(err) => Err(err),
+        None => bail!("No server url to probe."),
+    }
+}
+
+fn probe_server(url: &str) -> Result<ServerInfo> {
+    let address = server_address(url);
+    let socket = address
+        .to_socket_addrs()
+        .with_context(|| format!("Cannot resolve '{}'", address))?
+        .next()
+        .with_context(|| format!("Cannot resolve '{}'", address))?;
+
+    let stream = TcpStream::connect_timeout(&socket, PROBE_TIMEOUT)?;
+    stream.set_read_timeout(Some(PROBE_TIMEOUT))?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    match line.trim().strip_prefix("INFO ") {
+        Some(info) => Ok(serde_json::from_str(info)?),
+        None => bail!("Unexpected greeting from '{}'", address),
+    }
+}
+
+// server_address strips the scheme and credentials of the url and adds the
+// default port
+fn server_address(url: &str) -> String {
+    let rest = match url.find("://") {
+        Some(i) => &url[i + 3..],
+        None => url,
+    };
+    let host = match rest.rfind('@') {
+        Some(at) => &rest[at + 1..],
+        None => rest,
+    };
+    let host = host.trim_end_matches('/');
+
+    match host.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+        _ => format!("{}:4222", host),
+    }
+}
@@ -0,0 +1,252 @@
+use serde::Deserialize;
+
+pub const SYSTEM_SUBJECTS: [&str; 4] = [
+    "$SYS.ACCOUNT.*.CONNECT",
+    "$SYS.ACCOUNT.*.DISCONNECT",
+    "$SYS.SERVER.*.STATSZ",
+    "$SYS.SERVER.*.CLIENT.AUTH.ERR",
+];
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerInfo {
+    pub name: String,
+    pub host: String,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ClientInfo {
+    pub host: String,
+    pub id: u64,
+    pub acc: String,
+    pub user: String,
+    pub name: String,
+    pub lang: String,
+    pub version: String,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DataStats {
+    pub msgs: u64,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Statsz {
+    pub cpu: f64,
+    pub connections: u64,
+    pub subscriptions: u64,
+    pub sent: DataStats,
+    pub received: DataStats,
+    pub slow_consumers: u64,
+}
+
+// advisory payload of connect, disconnect and auth error events
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ClientAdvisory {
+    pub server: ServerInfo,
+    pub client: ClientInfo,
+    pub sent: DataStats,
+    pub received: DataStats,
+    pub reason: String,
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerStatsMsg {
+    pub server: ServerInfo,
+    pub statsz: Statsz,
+}
+
+#[derive(Clone)]
+pub enum SystemEventKind {
+    Connect(ClientAdvisory),
+    Disconnect(ClientAdvisory),
+    AuthError(ClientAdvisory),
+    Stats(ServerStatsMsg),
+    Unknown,
+}
+
+#[derive(Clone)]
+pub struct SystemEvent {
+    pub time: String,
+    pub subject: String,
+    pub kind: SystemEventKind,
+    pub raw: String,
+}
+
+impl SystemEvent {
+    // decode the system event by its subject
+    pub fn decode(time: String, subject: String, raw: String) -> Self {
+        let kind = if subject.ends_with(".DISCONNECT") {
+            serde_json::from_str(&raw).map(SystemEventKind::Disconnect)
+        } else if subject.ends_with(".CONNECT") {
+            serde_json::from_str(&raw).map(SystemEventKind::Connect)
+        } else if subject.ends_with(".CLIENT.AUTH.ERR") {
+            serde_json::from_str(&raw).map(SystemEventKind::AuthError)
+        } else if subject.ends_with(".STATSZ") {
+            serde_json::from_str(&raw).map(SystemEventKind::Stats)
+        } else {
+            Ok(SystemEventKind::Unknown)
+        };
+
+        Self {
+            time,
+            subject,
+            kind: kind.unwrap_or(SystemEventKind::Unknown),
+            raw,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.kind {
+            SystemEventKind::Connect(_) => "CONNECT",
+            SystemEventKind::Disconnect(_) => "DISCONNECT",
+            SystemEventKind::AuthError(_) => "AUTH ERROR",
+            SystemEventKind::Stats(_) => "STATSZ",
+            SystemEventKind::Unknown => "UNKNOWN",
+        }
+    }
+
+    // who is the client or server the event is about
+    pub fn who(&self) -> String {
+        match &self.kind {
+            SystemEventKind::Connect(a)
+            | SystemEventKind::Disconnect(a)
+            | SystemEventKind::AuthError(a) => {
+                let name = match (a.client.name.is_empty(), a.client.user.is_empty()) {
+                    (false, _) => a.client.name.clone(),
+                    (true, false) => a.client.user.clone(),
+                    (true, true) => format!("cid:{}", a.client.id),
+                };
+                match a.client.acc.is_empty() {
+                    true => name,
+                    false => format!("{} ({})", name, a.client.acc),
+                }
+            }
+            SystemEventKind::Stats(s) => s.server.name.clone(),
+            SystemEventKind::Unknown => String::new(),
+        }
+    }
+
+    // where the client connected from
+    pub fn host(&self) -> String {
+        match &self.kind {
+            SystemEventKind::Connect(a)
+            | SystemEventKind::Disconnect(a)
+            | SystemEventKind::AuthError(a) => a.client.host.clone(),
+            SystemEventKind::Stats(s) => s.server.host.clone(),
+            SystemEventKind::Unknown => String::new(),
+        }
+    }
+
+    pub fn detail(&self) -> String {
+        match &self.kind {
+            SystemEventKind::Connect(a) => format!(
+                "{} {} on {}",
+                a.client.lang, a.client.version, a.server.name
+            ),
+            SystemEventKind::Disconnect(a) => format!(
+                "{} (sent {} msgs, received {} msgs)",
+                a.reason, a.sent.msgs, a.received.msgs
+            ),
+            SystemEventKind::AuthError(a) => a.reason.clone(),
+            SystemEventKind::Stats(s) => format!(
+                "cpu {:.1}%, {} conns, {} subs, in {} / out {} msgs, {} slow",
+                s.statsz.cpu,
+                s.statsz.connections,
+                s.statsz.subscriptions,
+                s.statsz.received.msgs,
+                s.statsz.sent.msgs,
+                s.statsz.slow_consumers
+            ),
+            SystemEventKind::Unknown => self.subject.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(subject: &str, raw: &str) -> SystemEvent {
+        SystemEvent::decode("12:00:00".to_string(), subject.to_string(), raw.to_string())
+    }
+
+    #[test]
+    fn decodes_connect() {
+        let event = decode(
+            "$SYS.ACCOUNT.APP.CONNECT",
+            r#"{"type": "io.nats.server.advisory.v1.client_connect",
+                "server": {"name": "n1", "host": "0.0.0.0"},
+                "client": {"host": "10.0.0.5", "id": 12, "acc": "APP", "user": "ada",
+                           "name": "orders", "lang": "go", "version": "1.31.0"}}"#,
+        );
+        assert_eq!(event.title(), "CONNECT");
+        assert_eq!(event.who(), "orders (APP)");
+        assert_eq!(event.host(), "10.0.0.5");
+        assert_eq!(event.detail(), "go 1.31.0 on n1");
+    }
+
+    #[test]
+    fn decodes_disconnect() {
+        let event = decode(
+            "$SYS.ACCOUNT.APP.DISCONNECT",
+            r#"{"client": {"id": 12, "user": "ada"}, "sent": {"msgs": 3, "bytes": 30},
+                "received": {"msgs": 4}, "reason": "Client Closed"}"#,
+        );
+        assert_eq!(event.title(), "DISCONNECT");
+        assert_eq!(event.who(), "ada");
+        assert_eq!(
+            event.detail(),
+            "Client Closed (sent 3 msgs, received 4 msgs)"
+        );
+    }
+
+    #[test]
+    fn decodes_statsz() {
+        let event = decode(
+            "$SYS.SERVER.NABC.STATSZ",
+            r#"{"server": {"name": "n1", "host": "10.0.0.1"},
+                "statsz": {"cpu": 12.34, "connections": 5, "subscriptions": 40,
+                           "sent": {"msgs": 100}, "received": {"msgs": 200}, "slow_consumers": 1}}"#,
+        );
+        assert_eq!(event.title(), "STATSZ");
+        assert_eq!(event.who(), "n1");
+        assert_eq!(event.host(), "10.0.0.1");
+        assert_eq!(
+            event.detail(),
+            "cpu 12.3%, 5 conns, 40 subs, in 200 / out 100 msgs, 1 slow"
+        );
+    }
+
+    #[test]
+    fn decodes_auth_error() {
+        let event = decode(
+            "$SYS.SERVER.NABC.CLIENT.AUTH.ERR",
+            r#"{"client": {"host": "10.0.0.9", "id": 7}, "reason": "Authentication Timeout"}"#,
+        );
+        assert_eq!(event.title(), "AUTH ERROR");
+        assert_eq!(event.who(), "cid:7");
+        assert_eq!(event.host(), "10.0.0.9");
+        assert_eq!(event.detail(), "Authentication Timeout");
+    }
+
+    #[test]
+    fn keeps_other_events_unknown() {
+        for (subject, raw) in [
+            ("$SYS.ACCOUNT.APP.LEAFNODE.CONNECT.X", "{}"),
+            ("$SYS.SERVER.NABC.SHUTDOWN", "{}"),
+            ("$SYS.ACCOUNT.APP.CONNECT", "not json"),
+        ] {
+            let event = decode(subject, raw);
+            assert_eq!(event.title(), "UNKNOWN", "{}", subject);
+            assert_eq!(event.who(), "");
+            assert_eq!(event.detail(), subject);
+            assert_eq!(event.raw, raw);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::nats::format_headers;
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use rand::{distributions::Uniform, Rng};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    sync::OnceLock,
+};
+
+const PLACEHOLDER: &str = r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)((?:\s+[^\s}]+)*)\s*\}\}";
+
+#[derive(Deserialize)]
+struct TemplateConfig {
+    name: String,
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    payload: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct TemplatesFile {
+    #[serde(default)]
+    template: Vec<TemplateConfig>,
+}
+
+// Template is a publish message with placeholders rendered on every send
+#[derive(Clone)]
+pub struct Template {
+    pub name: String,
+    pub subject: String,
+    pub payload: String,
+    pub headers: String,
+}
+
+// load reads [[template]] entries from a toml file
+pub fn load(path: &str) -> Result<Vec<Template>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Cannot read '{}'", path))?;
+    let file: TemplatesFile =
+        toml::from_str(&content).with_context(|| format!("Cannot parse '{}'", path))?;
+
+    Ok(file
+        .template
+        .into_iter()
+        .map(|t| Template {
+            name: t.name,
+            subject: t.subject,
+            payload: t.payload,
+            headers: format_headers(&t.headers.into_iter().collect::<Vec<_>>()),
+        })
+        .collect())
+}
+
+// variables returns the user defined placeholders of the texts in order of
+// appearance, which have to be prompted before rendering
+pub fn variables(texts: &[&str]) -> Vec<String> {
+    let placeholder = placeholder();
+    let mut variables = Vec::new();
+    for text in texts {
+        for caps in placeholder.captures_iter(text) {
+            let name = caps[1].to_string();
+            if !is_builtin(&name) && !variables.contains(&name) {
+                variables.push(name);
+            }
+        }
+    }
+
+    variables
+}
+
+// render replaces the placeholders with fresh values:
+// {{uuid}}, {{now_rfc3339}}, {{seq}}, {{random_int MIN MAX}} and user variables
+pub fn render(text: &str, seq: u64, variables: &HashMap<String, String>) -> Result<String> {
+    let placeholder = placeholder();
+    let mut error = None;
+
+    let rendered = placeholder.replace_all(text, |caps: &Captures| {
+        match placeholder_value(caps, seq, variables) {
+            Ok(value) => value,
+            Err(err) => {
+                error.get_or_insert(err);
+                String::new()
+            }
+        }
+    });
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(rendered.into_owned()),
+    }
+}
+
+fn placeholder_value(
+    caps: &Captures,
+    seq: u64,
+    variables: &HashMap<String, String>,
+) -> Result<String> {
+    let args = caps[2].split_whitespace().collect::<Vec<_>>();
+    let value = match &caps[1] {
+        "uuid" => uuid::Uuid::new_v4().to_string(),
+        "now_rfc3339" => Local::now().to_rfc3339(),
+        "seq" => seq.to_string(),
+        "random_int" => {
+            let (min, max) = match args.as_slice() {
+                [min, max] => (
+                    min.parse::<i64>().context("Invalid random_int minimum")?,
+                    max.parse::<i64>().context("Invalid random_int maximum")?,
+                ),
+                _ => bail!("random_int needs a minimum and a maximum"),
+            };
+            if min > max {
+                bail!("random_int minimum {} is above maximum {}", min, max);
+            }
+            rand::thread_rng()
+                .sample(Uniform::new_inclusive(min, max))
+                .to_string()
+        }
+        name => match variables.get(name) {
+            Some(value) => value.clone(),
+            None => bail!("Template variable '{}' has no value", name),
+        },
+    };
+
+    Ok(value)
+}
+
+fn placeholder() -> &'static Regex {
+    static PLACEHOLDER_REGEX: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER_REGEX.get_or_init(|| Regex::new(PLACEHOLDER).unwrap())
+}
+
+fn is_builtin(name: &str) -> bool {
+    matches!(name, "uuid" | "now_rfc3339" | "seq" | "random_int")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_builtins() {
+        let rendered = render("{{seq}}-{{ seq }}", 7, &HashMap::new()).unwrap();
+        assert_eq!(rendered, "7-7");
+
+        let uuid = render("{{uuid}}", 0, &HashMap::new()).unwrap();
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+
+        let now = render("{{now_rfc3339}}", 0, &HashMap::new()).unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(&now).is_ok());
+    }
+
+    #[test]
+    fn renders_random_int_within_bounds() {
+        for _ in 0..100 {
+            let value = render("{{random_int -2 2}}", 0, &HashMap::new()).unwrap();
+            assert!((-2..=2).contains(&value.parse::<i64>().unwrap()));
+        }
+        assert_eq!(
+            render("{{random_int 5 5}}", 0, &HashMap::new()).unwrap(),
+            "5"
+        );
+
+        let max = format!("{{{{random_int {} {}}}}}", i64::MAX, i64::MAX);
+        assert_eq!(
+            render(&max, 0, &HashMap::new()).unwrap(),
+            i64::MAX.to_string()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_random_int() {
+        for text in [
+            "{{random_int}}",
+            "{{random_int 1}}",
+            "{{random_int 1 2 3}}",
+            "{{random_int a 2}}",
+            "{{random_int 3 2}}",
+        ] {
+            assert!(render(text, 0, &HashMap::new()).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn renders_variables() {
+        let variables = vars(&[("user", "ada"), ("empty", "")]);
+        let rendered = render(r#"{"user": "{{user}}", "e": "{{empty}}"}"#, 0, &variables);
+        assert_eq!(rendered.unwrap(), r#"{"user": "ada", "e": ""}"#);
+
+        let err = render("{{missing}}", 0, &variables).unwrap_err();
+        assert_eq!(err.to_string(), "Template variable 'missing' has no value");
+    }
+
+    #[test]
+    fn keeps_text_that_is_not_a_placeholder() {
+        for text in [
+            "", "plain", "{seq}", "{{}}", "{{1seq}}", "{{seq", "{{ seq }",
+        ] {
+            assert_eq!(render(text, 1, &HashMap::new()).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn lists_variables_once_in_order() {
+        let texts = ["{{b}} {{seq}} {{a}}", "{{b}} {{uuid}} {{c x}}"];
+        assert_eq!(variables(&texts), vec!["b", "a", "c"]);
+    }
+}
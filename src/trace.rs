@@ -0,0 +1,281 @@
+use crate::message::Message;
+use rand::Rng;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+// requests without a reply after this duration are listed as unanswered
+pub const UNANSWERED_AFTER: Duration = Duration::from_secs(5);
+
+// requests without a reply are forgotten after this duration, and the oldest
+// ones once there are MAX_PENDING of them
+const PENDING_EXPIRY: Duration = Duration::from_secs(60);
+const MAX_PENDING: usize = 10_000;
+
+// expired requests are dropped at most once per this interval
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+// upper bounds of the latency histogram buckets
+pub const BUCKETS: [Duration; 7] = [
+    Duration::from_millis(1),
+    Duration::from_millis(5),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+];
+
+// latency samples kept per subject for the percentiles
+const MAX_SAMPLES: usize = 10_000;
+
+// Latencies collects samples, the percentiles are read from a uniform sample
+// of at most MAX_SAMPLES, the other figures are exact
+#[derive(Default)]
+pub struct Latencies {
+    samples: Mutex<Vec<Duration>>,
+    // samples are sorted when the percentiles are read after a record
+    sorted: AtomicBool,
+    count: usize,
+    total: Duration,
+    max: Duration,
+    // BUCKETS plus one bucket for slower replies
+    pub histogram: [u64; BUCKETS.len() + 1],
+}
+
+impl Latencies {
+    pub fn record(&mut self, latency: Duration) {
+        let bucket = BUCKETS
+            .iter()
+            .position(|b| latency <= *b)
+            .unwrap_or(BUCKETS.len());
+        self.histogram[bucket] += 1;
+        self.count += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+
+        // reservoir sampling keeps every sample with the same chance
+        let samples = self.samples.get_mut().unwrap();
+        if samples.len() < MAX_SAMPLES {
+            samples.push(latency);
+        } else {
+            let i = rand::thread_rng().gen_range(0, self.count);
+            match samples.get_mut(i) {
+                Some(sample) => *sample = latency,
+                None => return,
+            }
+        }
+        self.sorted.store(false, Ordering::SeqCst);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn average(&self) -> Duration {
+        match self.count {
+            0 => Duration::default(),
+            n => self.total.div_f64(n as f64),
+        }
+    }
+
+    pub fn percentile(&self, p: f64) -> Duration {
+        let mut samples = self.samples.lock().unwrap();
+        if !self.sorted.swap(true, Ordering::SeqCst) {
+            samples.sort_unstable();
+        }
+        match samples.len() {
+            0 => Duration::default(),
+            n => samples[((n - 1) as f64 * p).round() as usize],
+        }
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+}
+
+pub struct Pending {
+    pub index: usize,
+    pub subject: String,
+    pub reply: String,
+    pub sent: Instant,
+}
+
+// Tracer pairs requests and replies seen on the wire by their reply subject
+#[derive(Default)]
+pub struct Tracer {
+    pending: HashMap<String, Pending>,
+    latencies: BTreeMap<String, Latencies>,
+    expired: Option<Instant>,
+}
+
+impl Tracer {
+    // track links the message at index with its request if it is a reply
+    // and remembers it if it is a request
+    pub fn track(&mut self, index: usize, messages: &mut [Message]) {
+        let msg = &messages[index];
+
+        if let Some(request) = self.pending.remove(&msg.subject) {
+            let latency = msg.received.duration_since(request.sent);
+            self.latencies
+                .entry(request.subject)
+                .or_default()
+                .record(latency);
+
+            messages[index].link = Some(request.index);
+            messages[request.index].link = Some(index);
+            messages[request.index].latency = Some(latency);
+        }
+
+        let msg = &messages[index];
+        if let Some(reply) = &msg.reply {
+            self.expire(msg.received);
+            self.pending.insert(
+                reply.clone(),
+                Pending {
+                    index,
+                    subject: msg.subject.clone(),
+                    reply: reply.clone(),
+                    sent: msg.received,
+                },
+            );
+        }
+    }
+
+    // expire drops the requests older than PENDING_EXPIRY and makes room for
+    // one more request
+    fn expire(&mut self, now: Instant) {
+        let due = match self.expired {
+            Some(expired) => now.saturating_duration_since(expired) >= EXPIRY_INTERVAL,
+            None => true,
+        };
+        if due {
+            self.expired = Some(now);
+            self.pending
+                .retain(|_, p| now.saturating_duration_since(p.sent) < PENDING_EXPIRY);
+        }
+
+        if self.pending.len() >= MAX_PENDING {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by_key(|(_, p)| p.index)
+                .map(|(reply, _)| reply.clone());
+            if let Some(reply) = oldest {
+                self.pending.remove(&reply);
+            }
+        }
+    }
+
+    // requests waiting longer than UNANSWERED_AFTER, oldest first
+    pub fn unanswered(&self) -> Vec<&Pending> {
+        let mut unanswered = self
+            .pending
+            .values()
+            .filter(|p| p.sent.elapsed() >= UNANSWERED_AFTER)
+            .collect::<Vec<_>>();
+        unanswered.sort_by_key(|p| p.index);
+
+        unanswered
+    }
+
+    pub fn latencies(&self) -> &BTreeMap<String, Latencies> {
+        &self.latencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(reply: &str, received: Instant) -> Message {
+        let mut msg = Message::new("svc".to_string(), Some(reply.to_string()), Vec::new(), b"");
+        msg.received = received;
+        msg
+    }
+
+    #[test]
+    fn reads_percentiles_after_record() {
+        let mut latencies = Latencies::default();
+        for ms in [30, 10, 20] {
+            latencies.record(Duration::from_millis(ms));
+        }
+        assert_eq!(latencies.percentile(0.5), Duration::from_millis(20));
+
+        latencies.record(Duration::from_millis(1));
+        assert_eq!(latencies.percentile(0.0), Duration::from_millis(1));
+        assert_eq!(latencies.max(), Duration::from_millis(30));
+        assert_eq!(latencies.average(), Duration::from_millis(61) / 4);
+        assert_eq!(latencies.histogram[0], 1);
+    }
+
+    #[test]
+    fn bounds_samples() {
+        let mut latencies = Latencies::default();
+        for ms in 0..2 * MAX_SAMPLES as u64 {
+            latencies.record(Duration::from_millis(ms));
+        }
+        assert_eq!(latencies.samples.lock().unwrap().len(), MAX_SAMPLES);
+        assert_eq!(latencies.count(), 2 * MAX_SAMPLES);
+        assert_eq!(
+            latencies.max(),
+            Duration::from_millis(2 * MAX_SAMPLES as u64 - 1)
+        );
+
+        let median = latencies.percentile(0.5).as_millis() as f64;
+        assert!((median / MAX_SAMPLES as f64 - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn pairs_replies() {
+        let mut tracer = Tracer::default();
+        let mut messages = vec![
+            request("_INBOX.1", Instant::now()),
+            Message::new("_INBOX.1".to_string(), None, Vec::new(), b""),
+        ];
+        tracer.track(0, &mut messages);
+        tracer.track(1, &mut messages);
+
+        assert_eq!(messages[0].link, Some(1));
+        assert_eq!(messages[1].link, Some(0));
+        assert!(tracer.pending.is_empty());
+        assert_eq!(tracer.latencies()["svc"].count(), 1);
+    }
+
+    #[test]
+    fn expires_old_requests() {
+        let now = Instant::now();
+        let mut tracer = Tracer::default();
+        let mut messages = vec![request("_INBOX.1", now), request("_INBOX.2", now)];
+        tracer.track(0, &mut messages);
+        messages[1].received = now + PENDING_EXPIRY;
+        tracer.track(1, &mut messages);
+
+        assert_eq!(tracer.pending.len(), 1);
+        assert!(tracer.pending.contains_key("_INBOX.2"));
+    }
+
+    #[test]
+    fn caps_pending_requests() {
+        let now = Instant::now();
+        let mut tracer = Tracer::default();
+        let mut messages = (0..=MAX_PENDING)
+            .map(|i| request(&format!("_INBOX.{}", i), now))
+            .collect::<Vec<_>>();
+        for i in 0..messages.len() {
+            tracer.track(i, &mut messages);
+        }
+
+        assert_eq!(tracer.pending.len(), MAX_PENDING);
+        assert!(!tracer.pending.contains_key("_INBOX.0"));
+        assert!(tracer
+            .pending
+            .contains_key(&format!("_INBOX.{}", MAX_PENDING)));
+    }
+}